@@ -0,0 +1,591 @@
+use crate::Img2AscError;
+use crate::bitmap_font::BitmapFont;
+use crate::coloring::{AnsiColor, CellColor};
+use crate::palette::{ColorSpace, PALETTE_VGA, PALETTE_XTERM, Palette};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the glyphs of code page 437 for the control chars from 0x00 to 0x1f
+const CP437_LOW: [char; 32] = [
+    ' ', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼', //
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+];
+
+// the chars of code page 437 from 0x80 to 0xff
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+const SAUCE_ID: &[u8; 5] = b"SAUCE";
+const SAUCE_VERSION: &[u8; 2] = b"00";
+const SAUCE_SIZE: usize = 128;
+const SAUCE_EOF: u8 = 0x1a;
+const SAUCE_DATA_CHARACTER: u8 = 1;
+const SAUCE_FILE_ANSI: u8 = 1;
+const SAUCE_FLAG_ICE_COLORS: u8 = 0x01;
+const SAUCE_COMMENT_ID: &[u8; 5] = b"COMNT";
+const SAUCE_COMMENT_SIZE: usize = 64;
+
+// the width of DOS, if the SAUCE record has none
+const ANS_DEFAULT_WIDTH: u32 = 80;
+// the screen of an imported file, cursor moves and chars beyond are dropped
+const ANS_MAX_COLS: u32 = 1000;
+const ANS_MAX_ROWS: u32 = 10_000;
+const ANSI_ESCAPE: char = '\x1b';
+
+// the grey of DOS on black
+const ANS_DEFAULT_FG: u8 = 7;
+const ANS_DEFAULT_BG: u8 = 0;
+
+/// The SAUCE metadata of an ANSI art file, the size comes from the image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnsOptions {
+    pub title: String,
+    pub author: String,
+    pub group: String,
+    /// The date as `CCYYMMDD`.
+    pub date: String,
+    /// The font of the viewer like `IBM VGA`.
+    pub font: String,
+    /// Bright backgrounds instead of blinking text.
+    pub ice_colors: bool,
+}
+
+impl Default for AnsOptions {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            author: String::new(),
+            group: String::new(),
+            date: sauce_date(SystemTime::now()),
+            font: "IBM VGA".to_string(),
+            ice_colors: false,
+        }
+    }
+}
+
+// the civil date from the days since 1970 with the algorithm of Howard Hinnant
+fn sauce_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86400) as i64
+        + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}{:02}{:02}", year, month, day)
+}
+
+/// The byte of a char in code page 437, `None` if the char is not part of it.
+pub fn encode_cp437(c: char) -> Option<u8> {
+    match c {
+        ' '..='~' => Some(c as u8),
+        _ => CP437_HIGH
+            .iter()
+            .position(|h| *h == c)
+            .map(|n| 0x80 + n as u8),
+    }
+}
+
+/// The char of a byte in code page 437, line breaks, escape and end of file are kept
+/// as control chars.
+pub fn decode_cp437(byte: u8) -> char {
+    match byte {
+        b'\n' | b'\r' | 0x1a | 0x1b => byte as char,
+        0x00..=0x1f => CP437_LOW[byte as usize],
+        0x7f => '⌂',
+        0x20..=0x7e => byte as char,
+        _ => CP437_HIGH[byte as usize - 0x80],
+    }
+}
+
+// the sub-cell blocks, braille and other chars without CP437 byte become the shade
+// with the same ink coverage in the built-in font
+fn encode_ans_char(c: char, font: &BitmapFont) -> u8 {
+    if let Some(byte) = encode_cp437(c) {
+        return byte;
+    }
+    let replacement = match c {
+        '╱' => '/',
+        '╲' => '\\',
+        _ => match font.coverage(c) {
+            Some(coverage) if coverage < 0.125 => ' ',
+            Some(coverage) if coverage < 0.375 => '░',
+            Some(coverage) if coverage < 0.625 => '▒',
+            Some(coverage) if coverage < 0.875 => '▓',
+            Some(_) => '█',
+            None => '?',
+        },
+    };
+    encode_cp437(replacement).unwrap_or(b'?')
+}
+
+fn write_field(record: &mut Vec<u8>, text: &str, size: usize, fill: u8) {
+    let mut bytes: Vec<u8> = text
+        .chars()
+        .map(|c| encode_cp437(c).unwrap_or(b'?'))
+        .take(size)
+        .collect();
+    bytes.resize(size, fill);
+    record.extend_from_slice(&bytes);
+}
+
+/// The SAUCE record of a file with `file_size` bytes, `cols` and `rows` are the size
+/// of the ansi image in chars.
+pub fn create_sauce_record(
+    file_size: usize,
+    cols: u32,
+    rows: u32,
+    options: &AnsOptions,
+) -> Vec<u8> {
+    let mut record = Vec::with_capacity(SAUCE_SIZE);
+    record.extend_from_slice(SAUCE_ID);
+    record.extend_from_slice(SAUCE_VERSION);
+    write_field(&mut record, &options.title, 35, b' ');
+    write_field(&mut record, &options.author, 20, b' ');
+    write_field(&mut record, &options.group, 20, b' ');
+    write_field(&mut record, &options.date, 8, b' ');
+    record.extend_from_slice(&(file_size as u32).to_le_bytes());
+    record.push(SAUCE_DATA_CHARACTER);
+    record.push(SAUCE_FILE_ANSI);
+    record.extend_from_slice(&(cols.min(u16::MAX as u32) as u16).to_le_bytes());
+    record.extend_from_slice(&(rows.min(u16::MAX as u32) as u16).to_le_bytes());
+    record.extend_from_slice(&[0; 4]);
+    // no comments
+    record.push(0);
+    record.push(if options.ice_colors {
+        SAUCE_FLAG_ICE_COLORS
+    } else {
+        0
+    });
+    write_field(&mut record, &options.font, 22, 0);
+    record
+}
+
+// bold makes the foreground bright, with iCE colors blink makes the background bright,
+// otherwise bright backgrounds are dark
+fn ans_sgr(fg: u8, bg: u8, ice_colors: bool) -> String {
+    let mut sgr = String::from("\x1b[0");
+    if fg >= 8 {
+        sgr.push_str(";1");
+    }
+    if bg >= 8 && ice_colors {
+        sgr.push_str(";5");
+    }
+    sgr.push_str(&format!(";{};{}m", 30 + fg % 8, 40 + bg % 8));
+    sgr
+}
+
+/// Writes the ascii image as ANSI art in code page 437 with a SAUCE record of `cols`
+/// chars width. Colors of the 16 color palette keep their index, all others get the
+/// nearest color of the VGA palette. Every line ends with a line break, after a full
+/// line the cursor waits behind the last column, so the break doesn't add an empty line.
+pub fn create_ans(
+    text: &str,
+    colors: Option<&[CellColor]>,
+    cols: u32,
+    options: &AnsOptions,
+) -> Vec<u8> {
+    let font = BitmapFont::embedded();
+    let vga = Palette::ansi16(&PALETTE_VGA, ColorSpace::Oklab);
+    let index = |color: &AnsiColor| match color.index {
+        Some(n) if n < 16 => n,
+        _ => vga.find_nearest(color.rgb.map(|v| v as f32 / 255.)).0,
+    };
+
+    let mut ans: Vec<u8> = Vec::new();
+    let mut colors = colors.map(|colors| colors.iter());
+    let mut rows = 0;
+
+    for line in text.lines() {
+        let mut last: Option<(u8, u8)> = None;
+        for c in line.chars() {
+            if let Some(color) = colors.as_mut().and_then(|colors| colors.next()) {
+                let attribute = (
+                    index(&color.fg),
+                    color.bg.map_or(ANS_DEFAULT_BG, |bg| index(&bg)),
+                );
+                // a space shows only the background, it may still end the one before
+                let visible =
+                    c != ' ' || color.bg.is_some() || last.is_some_and(|(_, bg)| bg != attribute.1);
+                if visible && last != Some(attribute) {
+                    ans.extend_from_slice(
+                        ans_sgr(attribute.0, attribute.1, options.ice_colors).as_bytes(),
+                    );
+                    last = Some(attribute);
+                }
+            }
+            ans.push(encode_ans_char(c, &font));
+        }
+        if last.is_some() {
+            ans.extend_from_slice(ans_sgr(ANS_DEFAULT_FG, ANS_DEFAULT_BG, false).as_bytes());
+        }
+        ans.extend_from_slice(b"\r\n");
+        rows += 1;
+    }
+
+    let file_size = ans.len();
+    ans.push(SAUCE_EOF);
+    ans.extend(create_sauce_record(file_size, cols, rows, options));
+    ans
+}
+
+/// The SAUCE metadata and the size in chars of an ANSI art file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SauceRecord {
+    pub options: AnsOptions,
+    pub cols: u32,
+    pub rows: u32,
+}
+
+fn read_field(data: &[u8]) -> String {
+    let text: String = data.iter().map(|b| decode_cp437(*b)).collect();
+    text.trim_end_matches([' ', '\0']).to_string()
+}
+
+/// Reads the SAUCE record at the end of a file, `None` if the file has none.
+pub fn parse_sauce_record(data: &[u8]) -> Option<SauceRecord> {
+    let record = data.get(data.len().checked_sub(SAUCE_SIZE)?..)?;
+    if !record.starts_with(SAUCE_ID) {
+        return None;
+    }
+    let read_u16 = |offset: usize| u16::from_le_bytes([record[offset], record[offset + 1]]) as u32;
+    Some(SauceRecord {
+        options: AnsOptions {
+            title: read_field(&record[7..42]),
+            author: read_field(&record[42..62]),
+            group: read_field(&record[62..82]),
+            date: read_field(&record[82..90]),
+            font: read_field(&record[106..128]),
+            ice_colors: record[105] & SAUCE_FLAG_ICE_COLORS != 0,
+        },
+        cols: read_u16(96),
+        rows: read_u16(98),
+    })
+}
+
+/// The cells of an imported ANSI art file, like the result of a conversion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnsiArt {
+    /// The chars, one line per row.
+    pub text: String,
+    /// The colors of the chars row by row, the palette colors with their index.
+    pub colors: Vec<CellColor>,
+    pub cols: u32,
+    pub rows: u32,
+    pub sauce: Option<SauceRecord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AnsiPen {
+    Default,
+    Index(u8),
+    Rgb([u8; 3]),
+}
+
+// the state of the terminal while the file is read
+struct AnsiScreen {
+    cells: Vec<Vec<Option<(char, CellColor)>>>,
+    x: u32,
+    y: u32,
+    saved: (u32, u32),
+    width: Option<u32>,
+    fg: AnsiPen,
+    bg: AnsiPen,
+    bold: bool,
+    blink: bool,
+    reverse: bool,
+    ice_colors: bool,
+    // ANSI art has a black background, text files the one of the terminal
+    default_bg: bool,
+    palette: [[u8; 3]; 16],
+    xterm: Palette,
+}
+
+impl AnsiScreen {
+    fn palette_color(&self, n: u8) -> AnsiColor {
+        AnsiColor {
+            rgb: self.palette[n as usize],
+            index: Some(n),
+        }
+    }
+
+    // the palette colors keep their index, bright makes the first 8 colors bright
+    fn pen_color(&self, pen: AnsiPen, bright: bool) -> Option<AnsiColor> {
+        match pen {
+            AnsiPen::Default => None,
+            AnsiPen::Index(n) if n < 8 && bright => Some(self.palette_color(n + 8)),
+            AnsiPen::Index(n) if n < 16 => Some(self.palette_color(n)),
+            AnsiPen::Index(n) => Some(AnsiColor {
+                rgb: self.xterm.entries()[n as usize - 16].1,
+                index: Some(n),
+            }),
+            AnsiPen::Rgb(rgb) => Some(AnsiColor::rgb(rgb)),
+        }
+    }
+
+    fn color(&self) -> CellColor {
+        let fg = self
+            .pen_color(self.fg, self.bold)
+            .unwrap_or(self.palette_color(ANS_DEFAULT_FG));
+        let bg = self.pen_color(self.bg, self.blink && self.ice_colors);
+        let bg = bg.or(self.default_bg.then(|| self.palette_color(ANS_DEFAULT_BG)));
+        if self.reverse {
+            CellColor {
+                fg: bg.unwrap_or(self.palette_color(ANS_DEFAULT_BG)),
+                bg: Some(fg),
+            }
+        } else {
+            CellColor { fg, bg }
+        }
+    }
+
+    // like a terminal the cursor stops at the last column and row
+    fn move_to(&mut self, x: u32, y: u32) {
+        let cols = self.width.unwrap_or(ANS_MAX_COLS);
+        self.x = x.min(cols - 1);
+        self.y = y.min(ANS_MAX_ROWS);
+    }
+
+    // the cursor stays behind the last column until the next char, so a line break
+    // after a full line doesn't add an empty line
+    fn put(&mut self, c: char) {
+        if self.width.is_some_and(|w| self.x >= w) {
+            self.move_to(0, self.y.saturating_add(1));
+        }
+        if self.x >= ANS_MAX_COLS || self.y >= ANS_MAX_ROWS {
+            return;
+        }
+        let (x, y) = (self.x as usize, self.y as usize);
+        if self.cells.len() <= y {
+            self.cells.resize(y + 1, Vec::new());
+        }
+        if self.cells[y].len() <= x {
+            self.cells[y].resize(x + 1, None);
+        }
+        self.cells[y][x] = Some((c, self.color()));
+        self.x += 1;
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[u32]) {
+        let mut params = params.iter().copied();
+        while let Some(param) = params.next() {
+            match param {
+                0 => {
+                    self.fg = AnsiPen::Default;
+                    self.bg = AnsiPen::Default;
+                    self.bold = false;
+                    self.blink = false;
+                    self.reverse = false;
+                }
+                1 => self.bold = true,
+                5 | 6 => self.blink = true,
+                7 => self.reverse = true,
+                22 => self.bold = false,
+                25 => self.blink = false,
+                27 => self.reverse = false,
+                30..=37 => self.fg = AnsiPen::Index((param - 30) as u8),
+                40..=47 => self.bg = AnsiPen::Index((param - 40) as u8),
+                90..=97 => self.fg = AnsiPen::Index((param - 90 + 8) as u8),
+                100..=107 => self.bg = AnsiPen::Index((param - 100 + 8) as u8),
+                39 => self.fg = AnsiPen::Default,
+                49 => self.bg = AnsiPen::Default,
+                38 | 48 => {
+                    let pen = match params.next() {
+                        Some(5) => params.next().map(|n| AnsiPen::Index(n.min(255) as u8)),
+                        Some(2) => {
+                            let rgb = [params.next(), params.next(), params.next()];
+                            Some(AnsiPen::Rgb(rgb.map(|v| v.unwrap_or(0).min(255) as u8)))
+                        }
+                        _ => None,
+                    };
+                    match (param, pen) {
+                        (38, Some(pen)) => self.fg = pen,
+                        (_, Some(pen)) => self.bg = pen,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn control_sequence(&mut self, params: &[u32], command: char) {
+        let n = params.first().copied().unwrap_or(0).max(1);
+        let (x, y) = (self.x, self.y);
+        match command {
+            'A' => self.move_to(x, y.saturating_sub(n)),
+            'B' => self.move_to(x, y.saturating_add(n)),
+            'C' => self.move_to(x.saturating_add(n), y),
+            'D' => self.move_to(x.saturating_sub(n), y),
+            'H' | 'f' => {
+                let col = params.get(1).copied().unwrap_or(1).max(1);
+                self.move_to(col - 1, n - 1);
+            }
+            // 2 clears the whole screen and moves the cursor home
+            'J' if params.first() == Some(&2) => {
+                self.cells.clear();
+                self.x = 0;
+                self.y = 0;
+            }
+            'K' => {
+                if let Some(row) = self.cells.get_mut(self.y as usize) {
+                    row.truncate(self.x as usize);
+                }
+            }
+            'm' => self.select_graphic_rendition(params),
+            's' => self.saved = (self.x, self.y),
+            'u' => self.move_to(self.saved.0, self.saved.1),
+            _ => {}
+        }
+    }
+}
+
+/// Reads ANSI art in code page 437 or UTF-8 text with ANSI colors like the output of
+/// the converter. Files with a SAUCE record or bytes that are no UTF-8 are code page 437,
+/// they wrap at the width of the record or 80 columns and have a black background.
+/// The 16 colors come from the `palette` or, without, from the VGA palette for code
+/// page 437 and the xterm palette for UTF-8. The 16 and 256 colors keep the index of
+/// their SGR code, so `create_ans` writes them with the same index again.
+/// The screen has at most 1000 columns and 10000 rows, the rest is left out.
+pub fn parse_ansi_art(data: &[u8], palette: Option<&[[u8; 3]; 16]>) -> AnsiArt {
+    let sauce = parse_sauce_record(data);
+    // the text ends with the end of file char before the comments and the SAUCE record
+    let mut end = data.len();
+    if sauce.is_some() {
+        end -= SAUCE_SIZE;
+        let comments = 5 + data[end + 104] as usize * SAUCE_COMMENT_SIZE;
+        if data[end + 104] > 0
+            && end >= comments
+            && data[end - comments..].starts_with(SAUCE_COMMENT_ID)
+        {
+            end -= comments;
+        }
+    }
+    let content = data[..end]
+        .split(|b| *b == SAUCE_EOF)
+        .next()
+        .unwrap_or_default();
+
+    let utf8 = match (&sauce, std::str::from_utf8(content)) {
+        (None, Ok(text)) => Some(text),
+        _ => None,
+    };
+    let chars: Vec<char> = match utf8 {
+        Some(text) => text.chars().collect(),
+        None => content.iter().map(|b| decode_cp437(*b)).collect(),
+    };
+
+    let width = match (&sauce, utf8) {
+        (Some(sauce), _) if sauce.cols > 0 => Some(sauce.cols.min(ANS_MAX_COLS)),
+        (_, Some(_)) => None,
+        _ => Some(ANS_DEFAULT_WIDTH),
+    };
+    let palette = match (palette, utf8) {
+        (Some(palette), _) => *palette,
+        (None, Some(_)) => PALETTE_XTERM,
+        (None, None) => PALETTE_VGA,
+    };
+    let mut screen = AnsiScreen {
+        cells: Vec::new(),
+        x: 0,
+        y: 0,
+        saved: (0, 0),
+        width,
+        fg: AnsiPen::Default,
+        bg: AnsiPen::Default,
+        bold: false,
+        blink: false,
+        reverse: false,
+        ice_colors: sauce.as_ref().is_some_and(|s| s.options.ice_colors),
+        default_bg: utf8.is_none(),
+        palette,
+        xterm: Palette::xterm256(ColorSpace::Oklab),
+    };
+
+    let mut chars = chars.into_iter().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ANSI_ESCAPE if chars.peek() == Some(&'[') => {
+                chars.next();
+                let mut params: Vec<u32> = Vec::new();
+                let mut param: Option<u32> = None;
+                for c in chars.by_ref() {
+                    match c {
+                        '0'..='9' => {
+                            let digit = c as u32 - '0' as u32;
+                            param =
+                                Some(param.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                        }
+                        ';' => params.push(param.take().unwrap_or(0)),
+                        // private modes like ?7h
+                        '?' | '=' | '>' => {}
+                        '\x40'..='\x7e' => {
+                            params.extend(param.take());
+                            screen.control_sequence(&params, c);
+                            break;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            ANSI_ESCAPE => {}
+            '\r' => screen.x = 0,
+            '\n' => screen.move_to(0, screen.y.saturating_add(1)),
+            _ => screen.put(c),
+        }
+    }
+
+    // empty rows at the end come from the last line break
+    while screen
+        .cells
+        .last()
+        .is_some_and(|row| row.iter().all(|cell| cell.is_none()))
+    {
+        screen.cells.pop();
+    }
+    screen.select_graphic_rendition(&[0]);
+    let blank = screen.color();
+    let cols =
+        width.unwrap_or_else(|| screen.cells.iter().map(|row| row.len()).max().unwrap_or(0) as u32);
+    let rows = screen.cells.len() as u32;
+
+    let mut text = String::new();
+    let mut colors = Vec::with_capacity((cols * rows) as usize);
+    for row in screen.cells.iter() {
+        for x in 0..cols as usize {
+            let (c, color) = row.get(x).copied().flatten().unwrap_or((' ', blank));
+            text.push(c);
+            colors.push(color);
+        }
+        text.push('\n');
+    }
+
+    AnsiArt {
+        text,
+        colors,
+        cols,
+        rows,
+        sauce,
+    }
+}
+
+/// Loads an ANSI art or text file, see `parse_ansi_art`.
+pub fn load_ansi_art(
+    filename: &str,
+    palette: Option<&[[u8; 3]; 16]>,
+) -> Result<AnsiArt, Img2AscError> {
+    Ok(parse_ansi_art(&fs::read(filename)?, palette))
+}
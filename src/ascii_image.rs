@@ -1,3 +1,7 @@
+use crate::bitmap_font::BitmapFont;
+use crate::gamma::lightness;
+use crate::pixel_buffer::{BitmapBuffer, GreyBuffer, PixelBuffer};
+
 const ASCII_CHARS_BLOCK: [char; 5] = ['█', '▓', '▒', '░', ' '];
 const ASCII_CHARS_EXTENDED: [char; 70] = [
     '$', '@', 'B', '%', '8', '&', 'W', 'M', '#', '*', 'o', 'a', 'h', 'k', 'b', 'd', 'p', 'q', 'w',
@@ -6,110 +10,320 @@ const ASCII_CHARS_EXTENDED: [char; 70] = [
     'i', '!', 'l', 'I', ';', ':', ',', '"', '^', '`', '\'', '.', ' ',
 ];
 const ASCII_CHARS_SIMPLE: [char; 10] = ['@', '%', '#', '*', '+', '=', '-', ':', '.', ' '];
+const ASCII_CHARS_DOT: [char; 2] = ['.', ' '];
+const ASCII_CHARS_MINIMAL: [char; 6] = ['@', '#', '+', ':', '.', ' '];
+const ASCII_CHARS_BINARY: [char; 2] = ['█', ' '];
+const ASCII_CHARS_BARS: [char; 9] = ['█', '▇', '▆', '▅', '▄', '▃', '▂', '▁', ' '];
+
+/// Names of the built-in char ramps for `get_named_ramp`.
+pub const ASCII_RAMP_NAMES: [&str; 7] = [
+    "bars", "binary", "block", "dot", "extended", "minimal", "simple",
+];
+
+// the braille dot bit of every pixel, row by row: the left column has the dots 1, 2, 3
+// and 7, the right column 4, 5, 6 and 8, so the pixels of a row are three bits apart
+const BRAILLE_SHIFT_VALUE: [u32; 8] = [0, 3, 1, 4, 2, 5, 6, 7];
+const BRAILLE_BASE: u32 = 0x2800;
+
+// upper left, upper right, lower left and lower right as bits
+const QUADRANT_CHARS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+// the columns of the sextants are the existing half blocks
+const SEXTANT_BASE: u32 = 0x1fb00;
+const SEXTANT_LEFT: u32 = 0b010101;
+const SEXTANT_RIGHT: u32 = 0b101010;
 
-const BRAILLE_SHIFT_VALUE: [u32; 8] = [0, 1, 2, 6, 3, 4, 5, 7];
 pub const ASCII_X_DOTS: usize = 2;
 pub const ASCII_Y_DOTS: usize = 4;
 
-#[derive(Debug, PartialEq)]
+const HALF_BLOCK_UPPER: char = '▀';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AsciiType {
     Block,
     Braille,
     Dot,
     Extended,
     Simple,
+    Custom,
+    /// Compares every cell with the glyphs of a font, needs `Converter::font`.
+    Glyph,
+    /// Line art from the edges of the image, blended with the simple ramp.
+    Edge,
+    /// Upper half blocks with the colors of two pixels as foreground and background.
+    HalfBlock,
+    Quadrant,
+    Sextant,
 }
 
-fn ascii_type_block(grey_value: u8) -> char {
-    let val = ((grey_value as f32 * 4.) / 255.).round() as usize;
-    ASCII_CHARS_BLOCK[val]
+/// The pixels of the resized image per char in x and y, the glyph mode uses the font size.
+pub fn get_cell_size(ascii_type: AsciiType) -> (u32, u32) {
+    match ascii_type {
+        AsciiType::Braille => (ASCII_X_DOTS as u32, ASCII_Y_DOTS as u32),
+        AsciiType::HalfBlock => (1, 2),
+        AsciiType::Quadrant => (2, 2),
+        AsciiType::Sextant => (2, 3),
+        _ => (1, 1),
+    }
 }
 
-fn get_vector_offset(x: u32, y: u32, width: u32) -> usize {
-    (width * y + x) as usize
+/// What happens with cells at the right and bottom border that are only partly in the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellFit {
+    /// Fill the missing pixels with the background.
+    Pad,
+    /// Leave out the partial cells.
+    Crop,
+    /// Scale the image to whole cells.
+    Stretch,
 }
 
-pub fn ascii_type_braille(img_vec: &[u8], width: u32, height: u32) -> String {
-    let mut braille_text = String::new();
-
-    for iy in (0..height).step_by(ASCII_Y_DOTS) {
-        for ix in (0..width).step_by(ASCII_X_DOTS) {
-            let mut braille_info: [u8; 8] = [0; 8];
+/// The cells of the chars over an image of any size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellGrid {
+    pub cols: u32,
+    pub rows: u32,
+    cell_w: u32,
+    cell_h: u32,
+    width: u32,
+    height: u32,
+    fit: CellFit,
+}
 
-            let mut info_counter: usize = 0;
-            for y in 0..ASCII_Y_DOTS as u32 {
-                for x in 0..ASCII_X_DOTS as u32 {
-                    if img_vec[get_vector_offset(ix + x, iy + y, width)] == 0 {
-                        braille_info[info_counter] = 1;
-                    }
+impl CellGrid {
+    pub fn new(width: u32, height: u32, cell_w: u32, cell_h: u32, fit: CellFit) -> CellGrid {
+        let count = |size: u32, cell: u32| match fit {
+            CellFit::Crop => size / cell,
+            CellFit::Pad | CellFit::Stretch => size.div_ceil(cell),
+        };
+        CellGrid {
+            cols: count(width, cell_w),
+            rows: count(height, cell_h),
+            cell_w,
+            cell_h,
+            width,
+            height,
+            fit,
+        }
+    }
 
-                    info_counter += 1;
-                }
-            }
+    pub fn cell_size(&self) -> (u32, u32) {
+        (self.cell_w, self.cell_h)
+    }
 
-            let mut braille = 0;
-            for n in 0..8 {
-                braille += braille_info[n] << BRAILLE_SHIFT_VALUE[n];
-            }
+    /// The size of the image the cells are laid over.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
 
-            let uni_vec: Vec<u16> = vec![braille as u16 + 10240];
-            let uni_char = char::decode_utf16(uni_vec)
-                .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
-                .collect::<String>();
-            braille_text.push_str(uni_char.as_str());
+    /// The image pixel for a pixel of a cell, `None` is outside of the image.
+    pub fn pixel(&self, col: u32, row: u32, x: u32, y: u32) -> Option<(u32, u32)> {
+        let px = col * self.cell_w + x;
+        let py = row * self.cell_h + y;
+        match self.fit {
+            CellFit::Stretch => Some((
+                (px as u64 * self.width as u64 / (self.cols * self.cell_w) as u64) as u32,
+                (py as u64 * self.height as u64 / (self.rows * self.cell_h) as u64) as u32,
+            )),
+            _ if px < self.width && py < self.height => Some((px, py)),
+            _ => None,
         }
-        braille_text.push('\n');
     }
+}
 
-    braille_text
+/// How the chars of a ramp are ordered, `Coverage` and `Even` need a font.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RampOrder {
+    Given,
+    Coverage,
+    Even,
 }
 
-fn ascii_type_dot(grey_value: u8) -> char {
-    if grey_value < 128 { '.' } else { ' ' }
+/// The char ramp of an ascii type, the first char is used for black and the last for white.
+/// The sub-cell types, glyph and half block have no ramp and custom ramps are not known here.
+pub fn get_ascii_ramp(ascii_type: AsciiType) -> Option<&'static [char]> {
+    match ascii_type {
+        AsciiType::Block => Some(&ASCII_CHARS_BLOCK),
+        AsciiType::Dot => Some(&ASCII_CHARS_DOT),
+        AsciiType::Extended => Some(&ASCII_CHARS_EXTENDED),
+        AsciiType::Simple | AsciiType::Edge => Some(&ASCII_CHARS_SIMPLE),
+        AsciiType::Braille
+        | AsciiType::Custom
+        | AsciiType::Glyph
+        | AsciiType::HalfBlock
+        | AsciiType::Quadrant
+        | AsciiType::Sextant => None,
+    }
 }
 
-fn ascii_type_extended(grey_value: u8) -> char {
-    let val = ((grey_value as f32 * 69.) / 255.).round() as usize;
-    ASCII_CHARS_EXTENDED[val]
+pub fn get_named_ramp(name: &str) -> Option<Vec<char>> {
+    let ramp: &[char] = match name.to_lowercase().as_str() {
+        "bars" => &ASCII_CHARS_BARS,
+        "binary" => &ASCII_CHARS_BINARY,
+        "block" => &ASCII_CHARS_BLOCK,
+        "dot" => &ASCII_CHARS_DOT,
+        "extended" => &ASCII_CHARS_EXTENDED,
+        "minimal" => &ASCII_CHARS_MINIMAL,
+        "simple" => &ASCII_CHARS_SIMPLE,
+        _ => return None,
+    };
+    Some(ramp.to_vec())
 }
 
-fn ascii_type_simple(grey_value: u8) -> char {
-    let val = ((grey_value as f32 * 9.) / 255.).round() as usize;
-    ASCII_CHARS_SIMPLE[val]
+/// Reads a char ramp from a text, line breaks are removed.
+pub fn parse_ramp(text: &str) -> Vec<char> {
+    text.chars().filter(|c| *c != '\n' && *c != '\r').collect()
 }
 
-pub fn create_ascii_image(img_vec: &[u8], ascii_type: AsciiType, width: u32) -> String {
-    let mut asc_image = String::new();
-    let mut pos: u32 = 0;
-
-    for grey_val in img_vec.iter() {
-        let ch = match ascii_type {
-            AsciiType::Block => ascii_type_block(*grey_val),
-            AsciiType::Dot => ascii_type_dot(*grey_val),
-            AsciiType::Extended => ascii_type_extended(*grey_val),
-            _ => ascii_type_simple(*grey_val),
-        };
+/// Sorts the chars of a ramp by the ink coverage of their glyphs, the most ink first.
+/// Chars without glyph are dropped, except the space that never has ink.
+/// With `even` the chars are picked again so that the steps of the CIELAB lightness
+/// between them are even, then a char can be used more than once.
+pub fn create_font_ramp(ramp: &[char], font: &BitmapFont, even: bool) -> Vec<char> {
+    let mut chars: Vec<(char, f32)> = Vec::new();
+    for c in ramp {
+        if chars.iter().any(|(n, _)| n == c) {
+            continue;
+        }
+        match font.coverage(*c) {
+            Some(coverage) => chars.push((*c, coverage)),
+            None if *c == ' ' => chars.push((*c, 0.)),
+            None => {}
+        }
+    }
+    chars.sort_by(|a, b| b.1.total_cmp(&a.1));
 
-        asc_image.push(ch);
+    if !even || chars.len() < 2 {
+        return chars.iter().map(|(c, _)| *c).collect();
+    }
+
+    // the glyphs are black on white, so the lightness comes from the uncovered part
+    let levels: Vec<(char, f32)> = chars
+        .iter()
+        .map(|(c, coverage)| (*c, lightness(1. - coverage)))
+        .collect();
+    let first = levels[0].1;
+    let last = levels[levels.len() - 1].1;
+
+    (0..levels.len())
+        .map(|n| {
+            let target = first + (last - first) * n as f32 / (levels.len() - 1) as f32;
+            levels
+                .iter()
+                .min_by(|a, b| (a.1 - target).abs().total_cmp(&(b.1 - target).abs()))
+                .map(|(c, _)| *c)
+                .unwrap_or(' ')
+        })
+        .collect()
+}
 
-        pos += 1;
-        if pos == width {
-            pos = 0;
-            asc_image.push('\n');
+pub(crate) fn ascii_ramp_char(grey_value: u8, ramp: &[char]) -> char {
+    let val = ((grey_value as f32 * (ramp.len() - 1) as f32) / 255.).round() as usize;
+    ramp[val]
+}
+
+// packs the black pixels of every cell into bits, row by row from the upper left corner,
+// pixels outside of the image are white
+fn ascii_type_cells(
+    bitmap: &BitmapBuffer,
+    cell_w: u32,
+    cell_h: u32,
+    fit: CellFit,
+    cell_char: impl Fn(u32) -> char,
+) -> String {
+    let grid = CellGrid::new(bitmap.width(), bitmap.height(), cell_w, cell_h, fit);
+    let mut cell_text = String::new();
+
+    for row in 0..grid.rows {
+        for col in 0..grid.cols {
+            let mut bits = 0;
+            for y in 0..cell_h {
+                for x in 0..cell_w {
+                    if let Some((px, py)) = grid.pixel(col, row, x, y)
+                        && !bitmap.get(px, py)
+                    {
+                        bits |= 1 << (y * cell_w + x);
+                    }
+                }
+            }
+            cell_text.push(cell_char(bits));
         }
+        cell_text.push('\n');
     }
 
-    asc_image
+    cell_text
+}
+
+/// The braille char for the dots of a 2x4 cell as bits, row by row from the upper left corner.
+pub fn braille_char(bits: u32) -> char {
+    let mut braille = 0;
+    for (n, shift) in BRAILLE_SHIFT_VALUE.iter().enumerate() {
+        braille |= (bits >> n & 1) << shift;
+    }
+    char::from_u32(BRAILLE_BASE + braille).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// The quadrant block for the pixels of a 2x2 cell as bits.
+pub fn quadrant_char(bits: u32) -> char {
+    QUADRANT_CHARS[bits as usize & 15]
+}
+
+/// The sextant block for the pixels of a 2x3 cell as bits. Uses the sextants of
+/// Unicode 13, the half and full blocks are not part of them.
+pub fn sextant_char(bits: u32) -> char {
+    match bits {
+        0 => ' ',
+        SEXTANT_LEFT => '▌',
+        SEXTANT_RIGHT => '▐',
+        63 => '█',
+        _ => {
+            let skipped = (bits > SEXTANT_LEFT) as u32 + (bits > SEXTANT_RIGHT) as u32;
+            char::from_u32(SEXTANT_BASE + bits - 1 - skipped).unwrap_or(char::REPLACEMENT_CHARACTER)
+        }
+    }
+}
+
+pub fn ascii_type_braille(bitmap: &BitmapBuffer, fit: CellFit) -> String {
+    ascii_type_cells(
+        bitmap,
+        ASCII_X_DOTS as u32,
+        ASCII_Y_DOTS as u32,
+        fit,
+        braille_char,
+    )
+}
+
+pub fn ascii_type_quadrant(bitmap: &BitmapBuffer, fit: CellFit) -> String {
+    ascii_type_cells(bitmap, 2, 2, fit, quadrant_char)
+}
+
+pub fn ascii_type_sextant(bitmap: &BitmapBuffer, fit: CellFit) -> String {
+    ascii_type_cells(bitmap, 2, 3, fit, sextant_char)
 }
 
-pub fn create_single_channel_vec(img_vec: &[u8], width: u32, height: u32) -> Vec<u8> {
-    let channels = img_vec.len() / (width * height) as usize;
-    let mut s_vec: Vec<u8> = Vec::with_capacity((width * height) as usize);
-    for y in 0..height {
-        for x in 0..width {
-            let offset = (width * y + x) as usize * channels;
-            s_vec.push(img_vec[offset]);
+/// Only upper half blocks, the pixels are in the colors of `create_half_block_colors`.
+pub fn ascii_type_half_block(grid: &CellGrid) -> String {
+    let mut half_block_text = String::new();
+    for _ in 0..grid.rows {
+        for _ in 0..grid.cols {
+            half_block_text.push(HALF_BLOCK_UPPER);
         }
+        half_block_text.push('\n');
     }
-    s_vec
+    half_block_text
+}
+
+pub fn create_ascii_image(image: &GreyBuffer, ramp: &[char]) -> String {
+    let mut asc_image = String::new();
+
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            asc_image.push(ascii_ramp_char(image.get(x, y), ramp));
+        }
+        asc_image.push('\n');
+    }
+
+    asc_image
 }
@@ -0,0 +1,477 @@
+use crate::Img2AscError;
+use crate::ascii_image::{braille_char, quadrant_char, sextant_char};
+use std::collections::HashMap;
+use std::fs;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TABLE: u8 = 0x02;
+const PSF1_SEPARATOR: u16 = 0xffff;
+const PSF1_START_SEQ: u16 = 0xfffe;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+const PSF2_HAS_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xff;
+const PSF2_START_SEQ: u8 = 0xfe;
+
+// the largest glyph width and height, and the farthest offset of a BDF glyph
+const FONT_MAX_SIZE: u32 = 256;
+
+// the embedded font has glyphs of 5x7 pixels with two rows for descenders,
+// one pixel right and one pixel above them are empty
+const EMBEDDED_WIDTH: u32 = 6;
+const EMBEDDED_HEIGHT: u32 = 12;
+const EMBEDDED_TOP: u32 = 1;
+
+// the printable ASCII chars from the space on, the rows with the left pixel as 0x10
+const EMBEDDED_GLYPHS: [[u8; 9]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04, 0x00, 0x00], // !
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a, 0x00, 0x00], // #
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04, 0x00, 0x00], // $
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03, 0x00, 0x00], // %
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d, 0x00, 0x00], // &
+    [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02, 0x00, 0x00], // (
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08, 0x00, 0x00], // )
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00, 0x00, 0x00], // *
+    [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00, 0x00, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08, 0x00, 0x00], // ,
+    [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c, 0x00, 0x00], // .
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00, 0x00, 0x00], // /
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e, 0x00, 0x00], // 0
+    [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00], // 1
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f, 0x00, 0x00], // 2
+    [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e, 0x00, 0x00], // 3
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02, 0x00, 0x00], // 4
+    [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e, 0x00, 0x00], // 5
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e, 0x00, 0x00], // 6
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08, 0x00, 0x00], // 7
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e, 0x00, 0x00], // 8
+    [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c, 0x00, 0x00], // 9
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00, 0x00, 0x00], // :
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08, 0x00, 0x00], // ;
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02, 0x00, 0x00], // <
+    [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00], // =
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08, 0x00, 0x00], // >
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04, 0x00, 0x00], // ?
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e, 0x00, 0x00], // @
+    [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00, 0x00], // A
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e, 0x00, 0x00], // B
+    [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e, 0x00, 0x00], // C
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c, 0x00, 0x00], // D
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f, 0x00, 0x00], // E
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10, 0x00, 0x00], // F
+    [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f, 0x00, 0x00], // G
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00, 0x00], // H
+    [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00], // I
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c, 0x00, 0x00], // J
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11, 0x00, 0x00], // K
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f, 0x00, 0x00], // L
+    [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11, 0x00, 0x00], // M
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11, 0x00, 0x00], // N
+    [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00], // O
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10, 0x00, 0x00], // P
+    [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d, 0x00, 0x00], // Q
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11, 0x00, 0x00], // R
+    [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e, 0x00, 0x00], // S
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00], // T
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00], // U
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00, 0x00], // V
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a, 0x00, 0x00], // W
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11, 0x00, 0x00], // X
+    [0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00], // Y
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f, 0x00, 0x00], // Z
+    [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e, 0x00, 0x00], // [
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00, 0x00, 0x00], // \
+    [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e, 0x00, 0x00], // ]
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00], // _
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f, 0x00, 0x00], // a
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e, 0x00, 0x00], // b
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e, 0x00, 0x00], // c
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f, 0x00, 0x00], // d
+    [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e, 0x00, 0x00], // e
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08, 0x00, 0x00], // f
+    [0x00, 0x00, 0x0f, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e], // g
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00, 0x00], // h
+    [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00], // i
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], // j
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12, 0x00, 0x00], // k
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00], // l
+    [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11, 0x00, 0x00], // m
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00, 0x00], // n
+    [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00], // o
+    [0x00, 0x00, 0x1e, 0x11, 0x11, 0x11, 0x1e, 0x10, 0x10], // p
+    [0x00, 0x00, 0x0f, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x01], // q
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10, 0x00, 0x00], // r
+    [0x00, 0x00, 0x0f, 0x10, 0x0e, 0x01, 0x1e, 0x00, 0x00], // s
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06, 0x00, 0x00], // t
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d, 0x00, 0x00], // u
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00, 0x00], // v
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a, 0x00, 0x00], // w
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x00, 0x00], // x
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e], // y
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f, 0x00, 0x00], // z
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02, 0x00, 0x00], // {
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00], // |
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08, 0x00, 0x00], // }
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00, 0x00, 0x00], // ~
+];
+
+// light, medium and dark shade
+const EMBEDDED_SHADES: [char; 3] = ['░', '▒', '▓'];
+// the lower eighth blocks from one to seven eighths
+const EMBEDDED_LOWER_BLOCKS: [char; 7] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+
+/// A monospaced bitmap font, every glyph has the size of one cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitmapFont {
+    width: u32,
+    height: u32,
+    glyphs: HashMap<char, Vec<bool>>,
+}
+
+fn invalid_font(reason: &str) -> Img2AscError {
+    Img2AscError::InvalidFont(reason.to_string())
+}
+
+fn check_glyph_size(width: u32, height: u32) -> Result<(), Img2AscError> {
+    if width == 0 || height == 0 {
+        return Err(invalid_font("the glyphs are empty"));
+    }
+    if width > FONT_MAX_SIZE || height > FONT_MAX_SIZE {
+        return Err(invalid_font("the glyphs are larger than 256 pixels"));
+    }
+    Ok(())
+}
+
+// keeps the glyph placement of BDF fonts within i32
+fn check_bdf_box([w, h, x, y]: [i32; 4]) -> Result<(), Img2AscError> {
+    let max = FONT_MAX_SIZE as i32;
+    if w < 0 || h < 0 || w > max || h > max || x.abs() > max || y.abs() > max {
+        return Err(invalid_font("the bounding box is larger than 256 pixels"));
+    }
+    Ok(())
+}
+
+impl BitmapFont {
+    /// Loads a BDF or PSF (version 1 or 2) font, the format is detected from the content.
+    pub fn from_file(filename: &str) -> Result<BitmapFont, Img2AscError> {
+        let data = fs::read(filename)?;
+        if data.starts_with(&PSF1_MAGIC) || data.starts_with(&PSF2_MAGIC) {
+            BitmapFont::parse_psf(&data)
+        } else if data.starts_with(b"STARTFONT") {
+            let text = String::from_utf8_lossy(&data);
+            BitmapFont::parse_bdf(&text)
+        } else {
+            Err(invalid_font("unknown font format, expected BDF or PSF"))
+        }
+    }
+
+    /// The built-in font with 6x12 pixels, it has the printable ASCII chars and the
+    /// blocks, braille and line chars of the ascii types.
+    pub fn embedded() -> BitmapFont {
+        let (width, height) = (EMBEDDED_WIDTH, EMBEDDED_HEIGHT);
+        let mut glyphs: HashMap<char, Vec<bool>> = HashMap::new();
+        let mut insert = |c: char, set: &dyn Fn(u32, u32) -> bool| {
+            let mut glyph = Vec::with_capacity((width * height) as usize);
+            for y in 0..height {
+                for x in 0..width {
+                    glyph.push(set(x, y));
+                }
+            }
+            glyphs.insert(c, glyph);
+        };
+
+        for (n, rows) in EMBEDDED_GLYPHS.iter().enumerate() {
+            insert(char::from(b' ' + n as u8), &|x, y| {
+                let row = y.wrapping_sub(EMBEDDED_TOP) as usize;
+                x < 5 && rows.get(row).is_some_and(|bits| bits & (0x10 >> x) != 0)
+            });
+        }
+
+        // the sub-cells are packed into bits like the pixels of the cells
+        for bits in 0..16 {
+            insert(quadrant_char(bits), &|x, y| {
+                bits >> ((y * 2 / height) * 2 + x * 2 / width) & 1 != 0
+            });
+        }
+        for bits in 0..64 {
+            insert(sextant_char(bits), &|x, y| {
+                bits >> ((y * 3 / height) * 2 + x * 2 / width) & 1 != 0
+            });
+        }
+        // a dot of 2x2 pixels in the upper left corner of every 3x3 sub-cell
+        for bits in 0..256 {
+            insert(braille_char(bits), &|x, y| {
+                x % 3 < 2 && y % 3 < 2 && bits >> ((y / 3) * 2 + x / 3) & 1 != 0
+            });
+        }
+
+        for (n, c) in EMBEDDED_SHADES.iter().enumerate() {
+            insert(*c, &|x, y| match n {
+                0 => x % 2 == 0 && y % 2 == 0,
+                1 => (x + y) % 2 == 0,
+                _ => x % 2 == 0 || y % 2 == 0,
+            });
+        }
+        for (n, c) in EMBEDDED_LOWER_BLOCKS.iter().enumerate() {
+            insert(*c, &|_, y| y >= height - height * (n as u32 + 1) / 8);
+        }
+
+        insert('─', &|_, y| y == height / 2);
+        insert('│', &|x, _| x == width / 2);
+        insert('╱', &|x, y| x == (height - 1 - y) * width / height);
+        insert('╲', &|x, y| x == y * width / height);
+
+        BitmapFont {
+            width,
+            height,
+            glyphs,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+
+    /// All chars with a glyph in the font, in no particular order.
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.glyphs.keys().copied()
+    }
+
+    /// Returns `true` if the pixel of the glyph is set, chars without glyph have no pixels set.
+    pub fn get(&self, c: char, x: u32, y: u32) -> bool {
+        self.glyphs
+            .get(&c)
+            .is_some_and(|glyph| glyph[(self.width * y + x) as usize])
+    }
+
+    /// The part of the cell covered by the glyph from 0.0 (empty) to 1.0 (full).
+    pub fn coverage(&self, c: char) -> Option<f32> {
+        let glyph = self.glyphs.get(&c)?;
+        let ink = glyph.iter().filter(|set| **set).count();
+        Some(ink as f32 / glyph.len() as f32)
+    }
+
+    /// Reads the text format of the X11 bitmap fonts. The glyphs are placed in a cell
+    /// with the size of `FONTBOUNDINGBOX`, glyphs without unicode encoding are skipped.
+    pub fn parse_bdf(text: &str) -> Result<BitmapFont, Img2AscError> {
+        let mut cell: Option<[i32; 4]> = None;
+        let mut glyphs: HashMap<char, Vec<bool>> = HashMap::new();
+
+        let mut encoding: Option<char> = None;
+        let mut bbx = [0i32; 4];
+        let mut bitmap: Option<Vec<Vec<u8>>> = None;
+
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let numbers: Vec<i32> = words.filter_map(|n| n.parse::<i32>().ok()).collect();
+
+            if let Some(rows) = bitmap.as_mut()
+                && keyword != "ENDCHAR"
+            {
+                let row = (0..keyword.len())
+                    .step_by(2)
+                    .map(|n| {
+                        keyword
+                            .get(n..n + 2)
+                            .and_then(|b| u8::from_str_radix(b, 16).ok())
+                    })
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(|| invalid_font("invalid hex value in BITMAP"))?;
+                rows.push(row);
+                continue;
+            }
+
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    let [w, h, x, y] = numbers[..] else {
+                        return Err(invalid_font("FONTBOUNDINGBOX needs 4 numbers"));
+                    };
+                    check_bdf_box([w, h, x, y])?;
+                    check_glyph_size(w as u32, h as u32)?;
+                    cell = Some([w, h, x, y]);
+                }
+                "STARTCHAR" => {
+                    encoding = None;
+                    bbx = [0; 4];
+                }
+                "ENCODING" => {
+                    encoding = numbers
+                        .first()
+                        .and_then(|code| u32::try_from(*code).ok())
+                        .and_then(char::from_u32);
+                }
+                "BBX" => {
+                    let [w, h, x, y] = numbers[..] else {
+                        return Err(invalid_font("BBX needs 4 numbers"));
+                    };
+                    check_bdf_box([w, h, x, y])?;
+                    bbx = [w, h, x, y];
+                }
+                "BITMAP" => bitmap = Some(Vec::new()),
+                "ENDCHAR" => {
+                    let rows = bitmap.take().unwrap_or_default();
+                    let Some(c) = encoding else {
+                        continue;
+                    };
+                    let [cell_w, cell_h, cell_x, cell_y] =
+                        cell.ok_or_else(|| invalid_font("FONTBOUNDINGBOX is missing"))?;
+
+                    // every row is padded to full bytes, the first pixel is the highest bit
+                    let [w, h, x, y] = bbx;
+                    let mut glyph = vec![false; (cell_w * cell_h) as usize];
+                    for (row, bytes) in rows.iter().take(h.max(0) as usize).enumerate() {
+                        let py = cell_h + cell_y - y - h + row as i32;
+                        for col in 0..w {
+                            let px = x - cell_x + col;
+                            let set = bytes
+                                .get(col as usize / 8)
+                                .is_some_and(|b| b & (0x80 >> (col % 8)) != 0);
+                            if set && (0..cell_w).contains(&px) && (0..cell_h).contains(&py) {
+                                glyph[(py * cell_w + px) as usize] = true;
+                            }
+                        }
+                    }
+                    glyphs.insert(c, glyph);
+                }
+                _ => {}
+            }
+        }
+
+        let [width, height, _, _] =
+            cell.ok_or_else(|| invalid_font("FONTBOUNDINGBOX is missing"))?;
+        if glyphs.is_empty() {
+            return Err(invalid_font("the font has no glyphs"));
+        }
+
+        Ok(BitmapFont {
+            width: width as u32,
+            height: height as u32,
+            glyphs,
+        })
+    }
+
+    /// Reads a Linux console font. Without unicode table the first 128 glyphs are used as ASCII.
+    pub fn parse_psf(data: &[u8]) -> Result<BitmapFont, Img2AscError> {
+        let read_u32 = |offset: usize| -> Result<u32, Img2AscError> {
+            data.get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| invalid_font("the font header is truncated"))
+        };
+
+        // (width, height, glyph count, glyph offset, unicode table)
+        let (width, height, length, offset, table) = if data.starts_with(&PSF1_MAGIC) {
+            let mode = *data
+                .get(2)
+                .ok_or_else(|| invalid_font("the font header is truncated"))?;
+            let height = *data
+                .get(3)
+                .ok_or_else(|| invalid_font("the font header is truncated"))?;
+            let length = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+            (8, height as u32, length, 4, mode & PSF1_MODE_HAS_TABLE != 0)
+        } else if data.starts_with(&PSF2_MAGIC) {
+            let offset = read_u32(8)? as usize;
+            let flags = read_u32(12)?;
+            let length = read_u32(16)?;
+            let height = read_u32(24)?;
+            let width = read_u32(28)?;
+            (width, height, length, offset, flags & PSF2_HAS_TABLE != 0)
+        } else {
+            return Err(invalid_font("no PSF magic number"));
+        };
+
+        check_glyph_size(width, height)?;
+        if length == 0 {
+            return Err(invalid_font("the font has no glyphs"));
+        }
+
+        let row_bytes = width.div_ceil(8) as usize;
+        let glyph_bytes = row_bytes * height as usize;
+        let table_offset = glyph_bytes
+            .checked_mul(length as usize)
+            .and_then(|size| size.checked_add(offset))
+            .ok_or_else(|| invalid_font("the glyph data is truncated"))?;
+        let glyph_data = data
+            .get(offset..table_offset)
+            .ok_or_else(|| invalid_font("the glyph data is truncated"))?;
+
+        let glyph_list: Vec<Vec<bool>> = glyph_data
+            .chunks(glyph_bytes)
+            .map(|bytes| {
+                let mut glyph = Vec::with_capacity((width * height) as usize);
+                for row in bytes.chunks(row_bytes) {
+                    for x in 0..width as usize {
+                        glyph.push(row[x / 8] & (0x80 >> (x % 8)) != 0);
+                    }
+                }
+                glyph
+            })
+            .collect();
+
+        let mut glyphs: HashMap<char, Vec<bool>> = HashMap::new();
+        if !table {
+            for (n, glyph) in glyph_list.iter().take(128).enumerate() {
+                glyphs.insert(char::from(n as u8), glyph.clone());
+            }
+        } else if data.starts_with(&PSF1_MAGIC) {
+            let mut entries = data[table_offset..]
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]));
+            for glyph in glyph_list.iter() {
+                let mut in_sequence = false;
+                for value in entries.by_ref() {
+                    match value {
+                        PSF1_SEPARATOR => break,
+                        PSF1_START_SEQ => in_sequence = true,
+                        _ if in_sequence => {}
+                        _ => {
+                            if let Some(c) = char::from_u32(value as u32) {
+                                glyphs.entry(c).or_insert_with(|| glyph.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        } else {
+            let mut entries = data[table_offset..].split(|b| *b == PSF2_SEPARATOR);
+            for glyph in glyph_list.iter() {
+                let Some(entry) = entries.next() else {
+                    break;
+                };
+                // sequences of combined chars follow after the single chars
+                let single = entry
+                    .split(|b| *b == PSF2_START_SEQ)
+                    .next()
+                    .unwrap_or_default();
+                for c in String::from_utf8_lossy(single).chars() {
+                    if c != char::REPLACEMENT_CHARACTER {
+                        glyphs.entry(c).or_insert_with(|| glyph.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(BitmapFont {
+            width,
+            height,
+            glyphs,
+        })
+    }
+}
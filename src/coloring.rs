@@ -0,0 +1,284 @@
+use crate::ascii_image::CellGrid;
+use crate::dithering::{DitherOptions, create_palette_dither_image};
+use crate::gamma::linear_to_srgb;
+use crate::palette::Palette;
+use crate::pixel_buffer::{RgbaFloatBuffer, quantize_u8};
+
+const ANSI_RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    None,
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// A sRGB color, with the index if it is from a palette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnsiColor {
+    pub rgb: [u8; 3],
+    pub index: Option<u8>,
+}
+
+/// The colors of one char, without background the color of the terminal stays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellColor {
+    pub fg: AnsiColor,
+    pub bg: Option<AnsiColor>,
+}
+
+impl AnsiColor {
+    pub fn rgb(rgb: [u8; 3]) -> AnsiColor {
+        AnsiColor { rgb, index: None }
+    }
+
+    // the indexes below 16 have their own codes, foreground is 30 and background 40
+    fn sgr(&self, base: u8) -> String {
+        let [r, g, b] = self.rgb;
+        match self.index {
+            None => format!("{};2;{};{};{}", base + 8, r, g, b),
+            Some(n) if n < 8 => format!("{}", base + n),
+            Some(n) if n < 16 => format!("{}", base + 60 + n - 8),
+            Some(n) => format!("{};5;{}", base + 8, n),
+        }
+    }
+}
+
+// transparent pixels and pixels outside of the image are black like in the greyscale image
+fn get_color(image: &RgbaFloatBuffer, pixel: Option<(u32, u32)>, alpha_threshold: u8) -> [f32; 3] {
+    match pixel.map(|(x, y)| image.get_pixel(x, y)) {
+        Some(pxl) if quantize_u8(pxl[3]) >= alpha_threshold => [pxl[0], pxl[1], pxl[2]],
+        _ => [0., 0., 0.],
+    }
+}
+
+fn encode_color(color: [f32; 3], linear: bool) -> [u8; 3] {
+    color.map(|v| quantize_u8(if linear { linear_to_srgb(v) } else { v }))
+}
+
+/// The average color of every cell of the resized image as sRGB values.
+/// With `background` the cell color is the background and the chars are black ink,
+/// or white ink if the image is inverted.
+pub fn create_cell_colors(
+    image: &RgbaFloatBuffer,
+    grid: &CellGrid,
+    alpha_threshold: u8,
+    linear: bool,
+    background: bool,
+    invert: bool,
+) -> Vec<CellColor> {
+    let (cell_w, cell_h) = grid.cell_size();
+    let count = (cell_w * cell_h) as f32;
+    let ink = if invert { [255; 3] } else { [0; 3] };
+
+    let mut colors = Vec::with_capacity((grid.cols * grid.rows) as usize);
+    for row in 0..grid.rows {
+        for col in 0..grid.cols {
+            let mut sum = [0.; 3];
+            for y in 0..cell_h {
+                for x in 0..cell_w {
+                    let color = get_color(image, grid.pixel(col, row, x, y), alpha_threshold);
+                    for n in 0..3 {
+                        sum[n] += color[n];
+                    }
+                }
+            }
+            let color = encode_color(sum.map(|v| v / count), linear);
+            colors.push(if background {
+                CellColor {
+                    fg: AnsiColor::rgb(ink),
+                    bg: Some(AnsiColor::rgb(color)),
+                }
+            } else {
+                CellColor {
+                    fg: AnsiColor::rgb(color),
+                    bg: None,
+                }
+            });
+        }
+    }
+    colors
+}
+
+/// The upper pixel of every cell as foreground and the lower one as background color.
+pub fn create_half_block_colors(
+    image: &RgbaFloatBuffer,
+    grid: &CellGrid,
+    alpha_threshold: u8,
+    linear: bool,
+    invert: bool,
+) -> Vec<CellColor> {
+    let mut colors = Vec::with_capacity((grid.cols * grid.rows) as usize);
+    for row in 0..grid.rows {
+        for col in 0..grid.cols {
+            let [fg, bg] = [0, 1].map(|y| {
+                let color = get_color(image, grid.pixel(col, row, 0, y), alpha_threshold);
+                let color = encode_color(color, linear);
+                if invert {
+                    color.map(|v| 255 - v)
+                } else {
+                    color
+                }
+            });
+            colors.push(CellColor {
+                fg: AnsiColor::rgb(fg),
+                bg: Some(AnsiColor::rgb(bg)),
+            });
+        }
+    }
+    colors
+}
+
+/// Replaces every color with the nearest color of the palette. Colors that already are
+/// the entry of the palette with their index keep it.
+pub fn create_palette_colors(colors: &[CellColor], palette: &Palette) -> Vec<CellColor> {
+    let nearest = |color: AnsiColor| match color.index {
+        Some(index) if palette.entries().contains(&(index, color.rgb)) => color,
+        _ => {
+            let (index, rgb) = palette.find_nearest(color.rgb.map(|v| v as f32 / 255.));
+            AnsiColor {
+                rgb,
+                index: Some(index),
+            }
+        }
+    };
+    colors
+        .iter()
+        .map(|color| CellColor {
+            fg: nearest(color.fg),
+            bg: color.bg.map(nearest),
+        })
+        .collect()
+}
+
+/// Like `create_palette_colors`, but the foreground and/or background colors of the
+/// cells are dithered, the cells are `cols` wide. With both the foreground is above
+/// the background, like the pixels of the half blocks.
+pub fn create_palette_dither_colors(
+    colors: &[CellColor],
+    cols: u32,
+    palette: &Palette,
+    options: &DitherOptions,
+    dither_fg: bool,
+    dither_bg: bool,
+) -> Vec<CellColor> {
+    let mut result = create_palette_colors(colors, palette);
+    let layers = dither_fg as u32 + dither_bg as u32;
+    if layers == 0 || cols == 0 {
+        return result;
+    }
+
+    let rows = colors.len() as u32 / cols;
+    let mut image = RgbaFloatBuffer::new(cols, rows * layers);
+    let mut positions = Vec::with_capacity((cols * rows * layers) as usize);
+    for row in 0..rows {
+        for layer in 0..layers {
+            let background = !dither_fg || layer == 1;
+            for col in 0..cols {
+                let n = (row * cols + col) as usize;
+                let color = if background {
+                    colors[n].bg.unwrap_or(colors[n].fg)
+                } else {
+                    colors[n].fg
+                };
+                let [r, g, b] = color.rgb.map(|v| v as f32 / 255.);
+                image.put_pixel(col, row * layers + layer, [r, g, b, 1.]);
+                positions.push((n, background));
+            }
+        }
+    }
+
+    let dithered = create_palette_dither_image(&image, palette, options);
+    for ((n, background), (index, rgb)) in positions.into_iter().zip(dithered) {
+        let color = AnsiColor {
+            rgb,
+            index: Some(index),
+        };
+        if background {
+            result[n].bg = Some(color);
+        } else {
+            result[n].fg = color;
+        }
+    }
+    result
+}
+
+/// Chars of a line with the same colors, from the column `col` on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRun {
+    pub col: u32,
+    pub text: String,
+    /// `None` for the spaces without background at the start of the line.
+    pub color: Option<CellColor>,
+}
+
+// a space shows only the background, so it needs its colors only if it has a background
+// or if it ends the background before it
+fn is_visible(c: char, color: &CellColor, last: Option<&CellColor>) -> bool {
+    c != ' ' || color.bg.is_some() || last.is_some_and(|last| last.bg.is_some())
+}
+
+/// Splits every line of the text into runs of chars with the same colors, one color
+/// per char. Spaces without background join the run before them like in the ANSI text,
+/// unless that run has a background.
+pub fn create_color_runs(text: &str, colors: &[CellColor]) -> Vec<Vec<ColorRun>> {
+    let mut colors = colors.iter();
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        let mut runs: Vec<ColorRun> = Vec::new();
+        for (col, c) in line.chars().enumerate() {
+            let last = runs.last().and_then(|run| run.color);
+            let color = colors
+                .next()
+                .filter(|color| is_visible(c, color, last.as_ref()))
+                .copied();
+            match runs.last_mut() {
+                Some(run) if color.is_none() || run.color == color => run.text.push(c),
+                _ => runs.push(ColorRun {
+                    col: col as u32,
+                    text: c.to_string(),
+                    color,
+                }),
+            }
+        }
+        lines.push(runs);
+    }
+
+    lines
+}
+
+/// Adds the ANSI colors to the lines of the text, one color per char. Palette colors are
+/// written with their index, all others as 24 bit colors. The colors are only written
+/// if they change, spaces without background keep the last color unless it has a
+/// background. Every line ends with a reset.
+pub fn create_ansi_text(text: &str, colors: &[CellColor]) -> String {
+    let mut ansi_text = String::new();
+    let mut colors = colors.iter();
+
+    for line in text.lines() {
+        let mut last: Option<CellColor> = None;
+        for c in line.chars() {
+            let Some(color) = colors.next() else {
+                ansi_text.push(c);
+                continue;
+            };
+            if is_visible(c, color, last.as_ref()) && last != Some(*color) {
+                ansi_text.push_str(&format!("\x1b[{}", color.fg.sgr(30)));
+                if let Some(bg) = color.bg {
+                    ansi_text.push_str(&format!(";{}", bg.sgr(40)));
+                } else if last.is_some_and(|l| l.bg.is_some()) {
+                    ansi_text.push_str(";49");
+                }
+                ansi_text.push('m');
+                last = Some(*color);
+            }
+            ansi_text.push(c);
+        }
+        ansi_text.push_str(ANSI_RESET);
+        ansi_text.push('\n');
+    }
+
+    ansi_text
+}
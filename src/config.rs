@@ -1,47 +1,56 @@
-use crate::ascii_image::AsciiType;
-use crate::dithering::Dithering;
-use crate::greyscaling::GreyScale;
-use crate::resizing::ResizeType;
+use img2asc::ansi_art::AnsOptions;
+use img2asc::ascii_image::{
+    ASCII_RAMP_NAMES, AsciiType, CellFit, RampOrder, get_named_ramp, parse_ramp,
+};
+use img2asc::bitmap_font::BitmapFont;
+use img2asc::coloring::ColorMode;
+use img2asc::dithering::{DitherKernel, Dithering};
+use img2asc::edge_detection::{EdgeChars, EdgeDetection};
+use img2asc::glyph_matching::GlyphMatch;
+use img2asc::greyscaling::GreyScale;
+use img2asc::html_export::{HtmlOptions, TextStyle};
+use img2asc::palette::{
+    ColorSpace, PALETTE_NAMES, get_named_palette, load_palette, parse_hex_color,
+};
+use img2asc::resizing::ResizeType;
+use img2asc::svg_export::SvgOptions;
+use img2asc::{Converter, Img2AscError};
 use std::env;
+use std::fs;
 use std::process::exit;
 
-#[derive(Debug)]
+/// The file format of the ascii image.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Html,
+    Svg,
+    Png,
+    Ans,
+}
+
+#[derive(Debug, Default)]
 pub struct Configuration {
     pub filename: String,
     pub output: String,
-    pub threshold: u8,
-    pub alpha_threshold: u8,
-    pub invert: bool,
-    pub dither: Dithering,
-    pub grey_scale: GreyScale,
-    pub ascii_type: AsciiType,
-    pub ascii_width: u16,
-    pub ascii_height: u16,
-    pub resize_opt: ResizeType,
     pub show_ascii: bool,
-}
-
-impl Default for Configuration {
-    fn default() -> Self {
-        Self {
-            filename: String::new(),
-            output: String::new(),
-            threshold: 128,
-            alpha_threshold: 30,
-            invert: false,
-            dither: Dithering::TwoRowSierra,
-            grey_scale: GreyScale::Luminance,
-            ascii_type: AsciiType::Simple,
-            ascii_width: 80,
-            ascii_height: 0,
-            resize_opt: ResizeType::Bilinear,
-            show_ascii: false,
-        }
-    }
+    /// Read the file as ANSI art or text with ANSI colors instead of an image.
+    pub ansi_input: bool,
+    /// The 16 colors are set with --palette or --palette-file.
+    pub custom_palette: bool,
+    /// The chars are set with --charset, --charset-file or --ramp.
+    pub custom_ramp: bool,
+    pub format: OutputFormat,
+    pub style: TextStyle,
+    pub html: HtmlOptions,
+    pub svg: SvgOptions,
+    pub ans: AnsOptions,
+    pub converter: Converter,
 }
 
 impl Configuration {
-    pub fn parse() -> Configuration {
+    pub fn parse() -> Result<Configuration, Img2AscError> {
         let mut cfg = Configuration::default();
 
         let mut args = env::args();
@@ -51,72 +60,221 @@ impl Configuration {
 
         if args.len() < 1 {
             println!("\nNot enough arguments, try: {} --help\n", prg_name);
-            exit(99);
+            return Err(Img2AscError::MissingArgument("FILE".to_string()));
         }
 
         while let Some(arg) = args.next() {
             match arg[..].to_lowercase().as_str() {
                 "-a" | "--ascii" => {
-                    let next_arg = args.next().unwrap().to_uppercase();
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
                     match next_arg.as_str() {
-                        "1" | "BLO" | "BLOCK" => cfg.ascii_type = AsciiType::Block,
-                        "2" | "BRA" | "BRAILLE" => cfg.ascii_type = AsciiType::Braille,
-                        "3" | "DOT" => cfg.ascii_type = AsciiType::Dot,
-                        "4" | "EXT" | "EXTENDED" => cfg.ascii_type = AsciiType::Extended,
-                        "5" | "SIM" | "SIMPLE" => cfg.ascii_type = AsciiType::Simple,
-                        _ => println!("Unknown positional argument {} for ascii type.", next_arg),
+                        "1" | "BLO" | "BLOCK" => cfg.converter.ascii_type = AsciiType::Block,
+                        "2" | "BRA" | "BRAILLE" => cfg.converter.ascii_type = AsciiType::Braille,
+                        "3" | "DOT" => cfg.converter.ascii_type = AsciiType::Dot,
+                        "4" | "EXT" | "EXTENDED" => cfg.converter.ascii_type = AsciiType::Extended,
+                        "5" | "SIM" | "SIMPLE" => cfg.converter.ascii_type = AsciiType::Simple,
+                        "6" | "GLY" | "GLYPH" => cfg.converter.ascii_type = AsciiType::Glyph,
+                        "7" | "EDG" | "EDGE" => cfg.converter.ascii_type = AsciiType::Edge,
+                        "8" | "HAL" | "HALFBLOCK" => {
+                            cfg.converter.ascii_type = AsciiType::HalfBlock
+                        }
+                        "9" | "QUA" | "QUADRANT" => cfg.converter.ascii_type = AsciiType::Quadrant,
+                        "10" | "SEX" | "SEXTANT" => cfg.converter.ascii_type = AsciiType::Sextant,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
                     }
                 }
 
                 "-at" | "--alpha-threshold" => {
-                    let next_arg = args.next().unwrap_or("?".to_string());
-                    cfg.alpha_threshold = next_arg.parse::<u8>().unwrap_or_else(|_| {
-                        println!(
-                            "Unknown positional argument {} for threshold, set to default (32).",
-                            next_arg
-                        );
-                        32
-                    });
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.alpha_threshold = next_arg
+                        .parse::<u8>()
+                        .map_err(|_| invalid_option(&arg, &next_arg))?;
+                }
+
+                "--background" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.style.background = parse_hex_color(&next_arg)
+                        .ok_or_else(|| invalid_option(&arg, &next_arg))?;
+                }
+
+                "--cell-fit" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "1" | "PAD" => cfg.converter.cell_fit = CellFit::Pad,
+                        "2" | "CRO" | "CROP" => cfg.converter.cell_fit = CellFit::Crop,
+                        "3" | "STR" | "STRETCH" => cfg.converter.cell_fit = CellFit::Stretch,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
+                }
+
+                "--char-width" => {
+                    cfg.svg.char_width = parse_size(&mut args, &arg)?;
+                }
+
+                "-c" | "--charset" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.set_charset(parse_ramp(&next_arg));
+                }
+
+                "--charset-file" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.set_charset(parse_ramp(&fs::read_to_string(&next_arg)?));
+                }
+
+                "--color" | "--colour" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "0" | "NONE" => cfg.converter.color = ColorMode::None,
+                        "1" | "TRUE" | "TRUECOLOR" => cfg.converter.color = ColorMode::TrueColor,
+                        "2" | "256" => cfg.converter.color = ColorMode::Ansi256,
+                        "3" | "16" => cfg.converter.color = ColorMode::Ansi16,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
+                }
+
+                "--color-bg" | "--colour-bg" => {
+                    cfg.converter.color_background = true;
+                }
+
+                "--color-space" | "--colour-space" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "1" | "LAB" | "CIELAB" => cfg.converter.color_space = ColorSpace::Cielab,
+                        "2" | "OKLAB" => cfg.converter.color_space = ColorSpace::Oklab,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
                 }
 
                 "-d" | "--dither" | "--dithering" => {
-                    let next_arg = args.next().unwrap().to_uppercase();
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    cfg.converter.dither_levels = true;
                     match next_arg.as_str() {
-                        "0" | "NONE" | "NODITHERING" => cfg.dither = Dithering::NoDither,
-                        "1" | "ATK" | "ATKINSON" => cfg.dither = Dithering::Atkinson,
-                        "2" | "BUR" | "BURKES" => cfg.dither = Dithering::Burkes,
-                        "3" | "FLO" | "FLOYDSTEINBERG" => cfg.dither = Dithering::FloydSteinberg,
-                        "4" | "JJN" => cfg.dither = Dithering::FloydSteinberg,
-                        "5" | "SIE" | "SIERRA" => cfg.dither = Dithering::FloydSteinberg,
-                        "6" | "SIL" | "SIERRALITE" => cfg.dither = Dithering::FloydSteinberg,
-                        "7" | "STU" | "STUCKI" => cfg.dither = Dithering::FloydSteinberg,
-                        "8" | "TRS" | "TWOROWSIERRA" => cfg.dither = Dithering::FloydSteinberg,
-                        _ => println!("Unknown positional argument {} for dithering.", next_arg),
+                        "0" | "NONE" | "NODITHERING" => cfg.converter.dither = Dithering::NoDither,
+                        "1" | "ATK" | "ATKINSON" => cfg.converter.dither = Dithering::Atkinson,
+                        "2" | "BUR" | "BURKES" => cfg.converter.dither = Dithering::Burkes,
+                        "3" | "FLO" | "FLOYDSTEINBERG" => {
+                            cfg.converter.dither = Dithering::FloydSteinberg
+                        }
+                        "4" | "JJN" => cfg.converter.dither = Dithering::Jjn,
+                        "5" | "SIE" | "SIERRA" => cfg.converter.dither = Dithering::Sierra,
+                        "6" | "SIL" | "SIERRALITE" => cfg.converter.dither = Dithering::SierraLite,
+                        "7" | "STU" | "STUCKI" => cfg.converter.dither = Dithering::Stucki,
+                        "8" | "TRS" | "TWOROWSIERRA" => {
+                            cfg.converter.dither = Dithering::TwoRowSierra
+                        }
+                        "9" | "BA2" | "BAYER2" => cfg.converter.dither = Dithering::Bayer2,
+                        "10" | "BA4" | "BAYER4" => cfg.converter.dither = Dithering::Bayer4,
+                        "11" | "BA8" | "BAYER8" => cfg.converter.dither = Dithering::Bayer8,
+                        "12" | "CL4" | "CLUSTER4" => {
+                            cfg.converter.dither = Dithering::ClusteredDot4
+                        }
+                        "13" | "CL8" | "CLUSTER8" => {
+                            cfg.converter.dither = Dithering::ClusteredDot8
+                        }
+                        "14" | "BLU" | "BLUENOISE" => cfg.converter.dither = Dithering::BlueNoise,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
                     }
                 }
 
+                "--dither-kernel" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.dither_kernel = Some(DitherKernel::from_file(&next_arg)?);
+                    cfg.converter.dither = Dithering::Custom;
+                    cfg.converter.dither_levels = true;
+                }
+
+                "-e" | "--edge" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "1" | "SOB" | "SOBEL" => {
+                            cfg.converter.edge_detection = EdgeDetection::Sobel
+                        }
+                        "2" | "CAN" | "CANNY" => {
+                            cfg.converter.edge_detection = EdgeDetection::Canny
+                        }
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
+                }
+
+                "--edge-blend" => {
+                    cfg.converter.edge_blend = true;
+                }
+
+                "--edge-chars" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "1" | "ASC" | "ASCII" => cfg.converter.edge_chars = EdgeChars::Ascii,
+                        "2" | "BOX" => cfg.converter.edge_chars = EdgeChars::Box,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
+                }
+
+                "--edge-threshold" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.edge_threshold = next_arg
+                        .parse::<u8>()
+                        .map_err(|_| invalid_option(&arg, &next_arg))?;
+                }
+
                 "-f" | "--file" | "--filename" => {
-                    let next_arg = args.next().unwrap();
+                    let next_arg = next_value(&mut args, &arg)?;
                     cfg.filename = next_arg;
                 }
 
+                "--font" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.font = Some(BitmapFont::from_file(&next_arg)?);
+                }
+
+                "--font-family" => {
+                    cfg.style.font_family = next_value(&mut args, &arg)?;
+                }
+
+                "--font-size" => {
+                    cfg.style.font_size = parse_size(&mut args, &arg)?;
+                }
+
+                "--format" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "1" | "TXT" | "TEXT" => cfg.format = OutputFormat::Text,
+                        "2" | "HTM" | "HTML" => cfg.format = OutputFormat::Html,
+                        "3" | "SVG" => cfg.format = OutputFormat::Svg,
+                        "4" | "PNG" => cfg.format = OutputFormat::Png,
+                        "5" | "ANS" | "ANSI" => cfg.format = OutputFormat::Ans,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
+                }
+
                 "-g" | "--grey" | "--greyscale" => {
-                    let next_arg = args.next().unwrap().to_uppercase();
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "1" | "AVG" | "AVERAGE" => cfg.converter.grey_scale = GreyScale::Average,
+                        "2" | "DES" | "DESATURATE" => {
+                            cfg.converter.grey_scale = GreyScale::Desaturate
+                        }
+                        "3" | "LUM" | "LUMINANCE" => {
+                            cfg.converter.grey_scale = GreyScale::Luminance
+                        }
+                        "4" | "MAX" | "MAXIMUM" => cfg.converter.grey_scale = GreyScale::Maximum,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
+                }
+
+                "--glyph-match" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
                     match next_arg.as_str() {
-                        "1" | "AVG" | "AVERAGE" => cfg.grey_scale = GreyScale::Average,
-                        "2" | "DES" | "DESATURATE" => cfg.grey_scale = GreyScale::Desaturate,
-                        "3" | "LUM" | "LUMINANCE" => cfg.grey_scale = GreyScale::Luminance,
-                        "4" | "MAX" | "MAXIMUM" => cfg.grey_scale = GreyScale::Maximum,
-                        _ => println!("Unknown positional argument {} for greyscale.", next_arg),
+                        "1" | "MSE" => cfg.converter.glyph_match = GlyphMatch::Mse,
+                        "2" | "SSIM" => cfg.converter.glyph_match = GlyphMatch::Ssim,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
                     }
                 }
 
                 "-h" | "--height" | "ascii_height" => {
-                    let next_arg = args.next().unwrap_or("?".to_string());
-                    cfg.ascii_height = next_arg.parse::<u16>().unwrap_or_else(|_| {
-                        println!("Unknown positional argument {} for ascii height.", next_arg);
-                        0
-                    });
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.ascii_height = next_arg
+                        .parse::<u16>()
+                        .map_err(|_| invalid_option(&arg, &next_arg))?;
                 }
 
                 "--help" => {
@@ -124,36 +282,103 @@ impl Configuration {
                     exit(0);
                 }
 
+                "--html-fragment" => {
+                    cfg.html.fragment = true;
+                }
+
+                "--ice-colors" | "--ice-colours" => {
+                    cfg.ans.ice_colors = true;
+                }
+
                 "-i" | "--invert" => {
-                    cfg.invert = true;
+                    cfg.converter.invert = true;
+                }
+
+                "--line-height" => {
+                    cfg.style.line_height = parse_size(&mut args, &arg)?;
                 }
 
                 "-r" | "--resize" => {
-                    let next_arg = args.next().unwrap().to_uppercase();
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
+                    match next_arg.as_str() {
+                        "1" | "BIC" | "BICUBIC" => cfg.converter.resize_opt = ResizeType::Bicubic,
+                        "2" | "BIL" | "BILINEAR" => cfg.converter.resize_opt = ResizeType::Bilinear,
+                        "3" | "NEA" | "NEAREST" => {
+                            cfg.converter.resize_opt = ResizeType::NearestNeighbour
+                        }
+                        "4" | "ARE" | "AREA" | "BOX" => cfg.converter.resize_opt = ResizeType::Area,
+                        "5" | "LAN" | "LANCZOS" => cfg.converter.resize_opt = ResizeType::Lanczos3,
+                        "6" | "MIT" | "MITCHELL" => cfg.converter.resize_opt = ResizeType::Mitchell,
+                        "7" | "CAT" | "CATMULLROM" => {
+                            cfg.converter.resize_opt = ResizeType::CatmullRom
+                        }
+                        _ => return Err(invalid_option(&arg, &next_arg)),
+                    }
+                }
+
+                "--serpentine" => {
+                    cfg.converter.serpentine = true;
+                }
+
+                "--srgb" => {
+                    cfg.converter.linear = false;
+                }
+
+                "--palette" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.palette = get_named_palette(&next_arg)
+                        .ok_or_else(|| invalid_option(&arg, &next_arg))?;
+                    cfg.custom_palette = true;
+                }
+
+                "--palette-file" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.palette = load_palette(&next_arg)?;
+                    cfg.custom_palette = true;
+                }
+
+                "--ramp" => {
+                    let next_arg = next_value(&mut args, &arg)?;
+                    let ramp =
+                        get_named_ramp(&next_arg).ok_or_else(|| invalid_option(&arg, &next_arg))?;
+                    cfg.set_charset(ramp);
+                }
+
+                "--ramp-order" => {
+                    let next_arg = next_value(&mut args, &arg)?.to_uppercase();
                     match next_arg.as_str() {
-                        "1" | "BIC" | "BICUBIC" => cfg.resize_opt = ResizeType::Bicubic,
-                        "2" | "BIL" | "BILINEAR" => cfg.resize_opt = ResizeType::Bilinear,
-                        "3" | "NEA" | "NEAREST" => cfg.resize_opt = ResizeType::NearestNeighbour,
-                        _ => println!(
-                            "Unknown positional argument {} for resize option.",
-                            next_arg
-                        ),
+                        "0" | "GIV" | "GIVEN" => cfg.converter.ramp_order = RampOrder::Given,
+                        "1" | "COV" | "COVERAGE" => cfg.converter.ramp_order = RampOrder::Coverage,
+                        "2" | "EVE" | "EVEN" => cfg.converter.ramp_order = RampOrder::Even,
+                        _ => return Err(invalid_option(&arg, &next_arg)),
                     }
                 }
 
+                "--sauce-author" => {
+                    cfg.ans.author = next_value(&mut args, &arg)?;
+                }
+
+                "--sauce-font" => {
+                    cfg.ans.font = next_value(&mut args, &arg)?;
+                }
+
+                "--sauce-group" => {
+                    cfg.ans.group = next_value(&mut args, &arg)?;
+                }
+
+                "--sauce-title" => {
+                    cfg.ans.title = next_value(&mut args, &arg)?;
+                }
+
                 "-s" | "--show" => {
                     cfg.show_ascii = true;
                 }
 
                 "-t" | "--threshold" => {
-                    let next_arg = args.next().unwrap_or("?".to_string());
-                    cfg.threshold = next_arg.parse::<u8>().unwrap_or_else(|_| {
-                        println!(
-                            "Unknown positional argument {} for threshold, set to default (128).",
-                            next_arg
-                        );
-                        128
-                    });
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.threshold = next_arg
+                        .parse::<u8>()
+                        .map_err(|_| invalid_option(&arg, &next_arg))?;
                 }
 
                 "-V" | "--version" => {
@@ -162,19 +387,18 @@ impl Configuration {
                 }
 
                 "-w" | "--width" | "ascii_width" => {
-                    let next_arg = args.next().unwrap_or("?".to_string());
-                    cfg.ascii_width = next_arg.parse::<u16>().unwrap_or_else(|_| {
-                        println!(
-                            "Unknown positional argument {} for ascii width, set to default (60).",
-                            next_arg
-                        );
-                        60
-                    });
+                    let next_arg = next_value(&mut args, &arg)?;
+                    cfg.converter.ascii_width = next_arg
+                        .parse::<u16>()
+                        .map_err(|_| invalid_option(&arg, &next_arg))?;
                 }
 
                 _ => {
                     if arg.starts_with('-') {
-                        println!("Unknown argument {}", arg);
+                        return Err(Img2AscError::InvalidOption {
+                            option: arg,
+                            value: String::new(),
+                        });
                     } else {
                         cfg.filename = arg;
                     }
@@ -182,27 +406,91 @@ impl Configuration {
             }
         }
 
+        if cfg.filename.is_empty() {
+            return Err(Img2AscError::MissingArgument("FILE".to_string()));
+        }
+
+        // the chars replace the ramp of every type whatever the order of the options,
+        // the glyph mode uses them as candidates
+        if cfg.custom_ramp && cfg.converter.ascii_type != AsciiType::Glyph {
+            cfg.converter.ascii_type = AsciiType::Custom;
+        }
+
+        let extension = cfg
+            .filename
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase());
+        cfg.ansi_input = matches!(extension.as_deref(), Some("ans" | "asc" | "txt"));
+
         if cfg.output.is_empty() {
             let mut split = cfg.filename.split('/');
             let fname = split.next_back().unwrap().to_string();
             split = fname.split('.');
-            cfg.output = split.nth(0).unwrap().to_string();
-            cfg.output.push_str(".txt");
+            let stem = split.nth(0).unwrap().to_string();
+            let extension = match cfg.format {
+                OutputFormat::Text => ".txt",
+                OutputFormat::Html => ".html",
+                OutputFormat::Svg => ".svg",
+                OutputFormat::Png => ".png",
+                OutputFormat::Ans => ".ans",
+            };
+            cfg.output = format!("{}{}", stem, extension);
+            // keeps the input file, if it has the same format
+            if cfg.output == fname {
+                cfg.output = format!("{}_ascii{}", stem, extension);
+            }
+        }
+        if let Some(fname) = cfg.filename.split('/').next_back() {
+            cfg.html.title = fname.to_string();
+            if cfg.ans.title.is_empty() {
+                cfg.ans.title = fname.to_string();
+            }
         }
 
-        cfg
+        Ok(cfg)
+    }
+
+    fn set_charset(&mut self, charset: Vec<char>) {
+        self.converter.charset = charset;
+        self.custom_ramp = true;
     }
 
     pub fn print(&self, width: u32, height: u32) {
         println!("Creating ascii image with this settings:");
         println!("----------------------------------------");
         println!("Ascii size:\t{} cols x {} rows", width, height);
-        println!("Ascii type:\t{}", Self::get_asc_type_desc(&self.ascii_type));
-        println!("Dithering:\t{}", Self::get_dither_desc(&self.dither));
-        println!("Greyscale:\t{}", Self::get_greyscale_desc(&self.grey_scale));
-        println!("Scale filter:\t{}", Self::get_resize_desc(&self.resize_opt));
-        println!("Invert colors:\t{}", self.invert);
-        println!("Threshold:\t{}", self.threshold);
+        println!(
+            "Ascii type:\t{}",
+            Self::get_asc_type_desc(&self.converter.ascii_type)
+        );
+        println!(
+            "Dithering:\t{}",
+            Self::get_dither_desc(&self.converter.dither)
+        );
+        println!(
+            "Greyscale:\t{}",
+            Self::get_greyscale_desc(&self.converter.grey_scale)
+        );
+        println!(
+            "Scale filter:\t{}",
+            Self::get_resize_desc(&self.converter.resize_opt)
+        );
+        println!("Invert colors:\t{}", self.converter.invert);
+        println!("Colors:\t\t{}", Self::get_color_desc(&self.converter.color));
+        println!("Format:\t\t{}", Self::get_format_desc(&self.format));
+        println!(
+            "Gamma:\t\t{}",
+            if self.converter.linear {
+                "linear light"
+            } else {
+                "sRGB"
+            }
+        );
+        println!(
+            "Ramp order:\t{}",
+            Self::get_ramp_order_desc(&self.converter.ramp_order)
+        );
+        println!("Threshold:\t{}", self.converter.threshold);
         println!();
     }
 
@@ -211,12 +499,48 @@ impl Configuration {
         println!("Usage:\n{} <FILE> [OPTIONS]\n", name);
         println!("Options:\n--------\n");
         println!("-a <TYPE>\t--ascii <TYPE>\t\ttype of ascii char set");
+        println!(
+            "  \t\t--background <COLOR>\tthe background color of HTML, SVG and PNG, e.g. #ffffff"
+        );
+        println!("  \t\t--cell-fit <TYPE>\tthe handling of partial cells at the border");
+        println!("  \t\t--char-width <NUM>\tthe char width of SVG as multiple of the font size");
+        println!("-c <CHARS>\t--charset <CHARS>\tuse the chars as ramp from black to white");
+        println!("  \t\t--charset-file <FILE>\tread the char ramp from a file");
+        println!("  \t\t--color <TYPE>\t\tthe ANSI colors of the chars");
+        println!("  \t\t--color-bg\t\tcolor the background instead of the chars");
+        println!("  \t\t--color-space <TYPE>\tthe color space to find the nearest palette color");
+        println!("-d <TYPE>\t--dither <TYPE>\t\tthe dithering algorithm");
+        println!("  \t\t--dither-kernel <FILE>\tload a custom error diffusion kernel");
+        println!("-e <TYPE>\t--edge <TYPE>\t\tthe edge detection for the edge type");
+        println!("  \t\t--edge-blend\t\tfill the cells without edge with the simple ramp");
+        println!("  \t\t--edge-chars <TYPE>\tthe chars for the edges");
+        println!("  \t\t--edge-threshold <NUM>\tthe gradient from 0 to 255 for an edge");
         println!("-f <FILE>\t--filename <FILE>\tpath and filename from the image file");
+        println!("  \t\t--font <FILE>\t\tload a BDF or PSF bitmap font for glyphs and PNG");
+        println!("  \t\t--font-family <NAME>\tthe CSS font family of HTML and SVG");
+        println!("  \t\t--font-size <NUM>\tthe font size of HTML and SVG in pixels");
+        println!("  \t\t--format <TYPE>\t\tthe file format of the ascii image");
         println!("-g <TYPE>\t--greyscale <TYPE>\tthe greyscale conversion algorithm");
+        println!("  \t\t--glyph-match <TYPE>\thow cells and glyphs are compared");
         println!("-h <NUM>\t--height <NUM>\t\tthe height of the ascii image");
         println!("  \t\t--help\t\t\tshow this help text");
+        println!("  \t\t--html-fragment\t\twrite only the <pre> instead of a whole HTML page");
+        println!("  \t\t--ice-colors\t\tbright backgrounds instead of blinking in ANSI art");
         println!("-i\t\t--invert\t\tinvert the image colors");
+        println!(
+            "  \t\t--line-height <NUM>\tthe line height of HTML and SVG as multiple of the font size"
+        );
+        println!("  \t\t--palette <NAME>\tthe 16 colors of the terminal");
+        println!("  \t\t--palette-file <FILE>\tread the 16 colors of the terminal from a file");
+        println!("  \t\t--ramp <NAME>\t\tuse a built-in char ramp");
+        println!("  \t\t--ramp-order <TYPE>\tsort the char ramp by the glyphs of the font");
         println!("-r <TYPE>\t--resize <TYPE>\t\tthe resize algorithm");
+        println!("  \t\t--sauce-author <NAME>\tthe author in the SAUCE record of ANSI art");
+        println!("  \t\t--sauce-font <NAME>\tthe font in the SAUCE record [default: IBM VGA]");
+        println!("  \t\t--sauce-group <NAME>\tthe group in the SAUCE record of ANSI art");
+        println!("  \t\t--sauce-title <TEXT>\tthe title in the SAUCE record [default: FILE]");
+        println!("  \t\t--serpentine\t\tscan every second row backwards on error diffusion");
+        println!("  \t\t--srgb\t\t\tcalculate with sRGB values instead of linear light");
         println!("-t <NUM>\t--threshold <NUM>\tthe threshold from black (0) to white (255)");
         println!("-V\t\t--version\t\tthe version of {}", name);
         println!("-w <NUM>\t--width <NUM>\t\tthe width of the ascii image");
@@ -227,6 +551,56 @@ impl Configuration {
         println!("| 3 | dot | only the . (dot)");
         println!("| 4 | ext | 70 chars");
         println!("| 5 | sim | 10 chars [default]");
+        println!("| 6 | gly | the glyph of the font that matches the cell best (needs --font)");
+        println!("| 7 | edg | line art from the edges of the image");
+        println!("| 8 | hal | colored half blocks, two pixels per char");
+        println!("| 9 | qua | quadrant blocks, 2x2 pixels per char");
+        println!("| 10 | sex | sextant blocks, 2x3 pixels per char (Unicode 13)");
+        println!();
+        println!("Char ramps:\n-----------");
+        println!("{}", ASCII_RAMP_NAMES.join(", "));
+        println!();
+        println!("Ramp orders:\n------------");
+        println!("| 0 | giv | as given [default]");
+        println!("| 1 | cov | by ink coverage of the glyphs");
+        println!("| 2 | eve | by ink coverage, with even lightness steps");
+        println!();
+        println!("Colors:\n-------");
+        println!("| 0 | none | no colors [default]");
+        println!("| 1 | true | 24 bit truecolor");
+        println!("| 2 | 256  | the 256 colors of xterm");
+        println!("| 3 | 16   | the 16 colors of the terminal, see --palette");
+        println!();
+        println!("Formats:\n--------");
+        println!("| 1 | txt  | text with ANSI colors [default]");
+        println!("| 2 | html | HTML page with a <pre> and colored <span> tags");
+        println!("| 3 | svg  | SVG with a <text> per line or colored run");
+        println!("| 4 | png  | image drawn with the --font or a built-in 6x12 font");
+        println!("| 5 | ans  | ANSI art in code page 437 with a SAUCE record");
+        println!();
+        println!("Color spaces:\n-------------");
+        println!("| 1 | lab   | CIELAB");
+        println!("| 2 | oklab | OKLab [default]");
+        println!();
+        println!("Palettes:\n---------");
+        println!("{} [default: xterm]", PALETTE_NAMES.join(", "));
+        println!();
+        println!("Cell fit:\n---------");
+        println!("| 1 | pad | keep the aspect ratio, fill partial cells with the background");
+        println!("| 2 | cro | keep the aspect ratio, leave out partial cells");
+        println!("| 3 | str | scale the image to whole cells [default]");
+        println!();
+        println!("Edge detection:\n---------------");
+        println!("| 1 | sob | Sobel [default]");
+        println!("| 2 | can | Canny (thin lines, less noise)");
+        println!();
+        println!("Edge chars:\n-----------");
+        println!("| 1 | asc | - / | \\ [default]");
+        println!("| 2 | box | ─ ╱ │ ╲");
+        println!();
+        println!("Glyph matching:\n---------------");
+        println!("| 1 | mse  | mean squared error, keeps the brightness [default]");
+        println!("| 2 | ssim | structural similarity, follows the edges");
         println!();
         println!("Dithering algorithms:\n---------------------");
         println!("| 0 | none  | no dithering");
@@ -238,6 +612,12 @@ impl Configuration {
         println!("| 6 | sil | Sierra Lite");
         println!("| 7 | stu | Stucki");
         println!("| 8 | trs | Two-Row Sierra [default]");
+        println!("| 9 | ba2 | Bayer 2x2 (ordered)");
+        println!("| 10 | ba4 | Bayer 4x4 (ordered)");
+        println!("| 11 | ba8 | Bayer 8x8 (ordered)");
+        println!("| 12 | cl4 | Clustered dot 4x4 (ordered)");
+        println!("| 13 | cl8 | Clustered dot 8x8 (ordered)");
+        println!("| 14 | blu | Blue noise 16x16 (ordered)");
         println!();
         println!("Greyscale algorithms:\n---------------------");
         println!("| 1 | avg | Average");
@@ -249,8 +629,36 @@ impl Configuration {
         println!("| 1 | bicu | Bi-Cubic (best)");
         println!("| 2 | bili | Bilinear [default]");
         println!("| 3 | near | Nearest Neighbour (fastest)");
+        println!("| 4 | area | Area average (box filter, no aliasing on downscaling)");
+        println!("| 5 | lanc | Lanczos-3 (sharpest)");
+        println!("| 6 | mitc | Mitchell-Netravali");
+        println!("| 7 | catm | Catmull-Rom");
+        println!();
+        println!("Exit codes:\n-----------");
+        println!("| 0 | success");
+        println!("| 1 | file can't be read or written, image can't be encoded");
+        println!("| 2 | unsupported image format");
+        println!("| 3 | image can't be decoded");
+        println!("| 4 | invalid option or option value");
+        println!("| 5 | missing argument or option value");
+        println!("| 6 | invalid image or ascii size");
+        println!("| 7 | invalid dither kernel file");
+        println!("| 8 | invalid font file");
+        println!("| 9 | invalid palette file");
+        println!();
+        println!("Dither kernel files:\n--------------------");
+        println!("One entry per line with x offset, y offset and weight, e.g. Floyd-Steinberg:");
+        println!("divisor = 16\n1 0 7\n-1 1 3\n0 1 5\n1 1 1");
+        println!("The x offset goes from -4 to 4, the y offset from 0 to 4.");
+        println!("Without divisor the sum of all weights is used, # starts a comment.");
         println!();
         println!("Hints:\n------");
+        println!(
+            "Files ending with .ans, .asc or .txt are read as ANSI art and written in the chosen format."
+        );
+        println!(
+            "Without -d only dot, braille, quadrant and sextant are dithered. With -d the char levels\nand the palette colors are dithered too."
+        );
         println!(
             "You can set width or height, the other size will be calculated by aspect ratio.\nIf you set both the ascii mage will be deformed to this size."
         );
@@ -264,6 +672,39 @@ impl Configuration {
             AsciiType::Dot => "Dot",
             AsciiType::Extended => "Extended",
             AsciiType::Simple => "Simple",
+            AsciiType::Custom => "Custom ramp",
+            AsciiType::Glyph => "Glyph matching",
+            AsciiType::Edge => "Edge line art",
+            AsciiType::HalfBlock => "Half blocks",
+            AsciiType::Quadrant => "Quadrant blocks",
+            AsciiType::Sextant => "Sextant blocks",
+        }
+    }
+
+    fn get_color_desc(t: &ColorMode) -> &'static str {
+        match t {
+            ColorMode::None => "None",
+            ColorMode::TrueColor => "Truecolor",
+            ColorMode::Ansi256 => "256 colors",
+            ColorMode::Ansi16 => "16 colors",
+        }
+    }
+
+    fn get_format_desc(t: &OutputFormat) -> &'static str {
+        match t {
+            OutputFormat::Text => "Text",
+            OutputFormat::Html => "HTML",
+            OutputFormat::Svg => "SVG",
+            OutputFormat::Png => "PNG",
+            OutputFormat::Ans => "ANSI art",
+        }
+    }
+
+    fn get_ramp_order_desc(t: &RampOrder) -> &'static str {
+        match t {
+            RampOrder::Given => "As given",
+            RampOrder::Coverage => "Ink coverage",
+            RampOrder::Even => "Ink coverage, even steps",
         }
     }
 
@@ -277,6 +718,13 @@ impl Configuration {
             Dithering::SierraLite => "Sierra Lite",
             Dithering::Stucki => "Stucki",
             Dithering::TwoRowSierra => "Two-Row Sierra",
+            Dithering::Bayer2 => "Bayer 2x2",
+            Dithering::Bayer4 => "Bayer 4x4",
+            Dithering::Bayer8 => "Bayer 8x8",
+            Dithering::ClusteredDot4 => "Clustered dot 4x4",
+            Dithering::ClusteredDot8 => "Clustered dot 8x8",
+            Dithering::BlueNoise => "Blue noise",
+            Dithering::Custom => "Custom kernel",
             _ => "No dithering",
         }
     }
@@ -295,6 +743,10 @@ impl Configuration {
             ResizeType::Bicubic => "Bi-Cubic",
             ResizeType::Bilinear => "Bilinear",
             ResizeType::NearestNeighbour => "Nearest Neighbour",
+            ResizeType::Area => "Area average",
+            ResizeType::Lanczos3 => "Lanczos-3",
+            ResizeType::Mitchell => "Mitchell-Netravali",
+            ResizeType::CatmullRom => "Catmull-Rom",
         }
     }
 
@@ -306,3 +758,25 @@ impl Configuration {
     //     (String::new(),String::new())
     // }
 }
+
+fn next_value(args: &mut env::Args, option: &str) -> Result<String, Img2AscError> {
+    args.next()
+        .ok_or_else(|| Img2AscError::MissingArgument(format!("value for {}", option)))
+}
+
+// a font size or line height, must be above zero
+fn parse_size(args: &mut env::Args, option: &str) -> Result<f32, Img2AscError> {
+    let value = next_value(args, option)?;
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite() && *v > 0.)
+        .ok_or_else(|| invalid_option(option, &value))
+}
+
+fn invalid_option(option: &str, value: &str) -> Img2AscError {
+    Img2AscError::InvalidOption {
+        option: option.to_string(),
+        value: value.to_string(),
+    }
+}
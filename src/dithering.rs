@@ -1,5 +1,11 @@
+use crate::error::Img2AscError;
+use crate::gamma::{linear_to_srgb, srgb_to_linear, srgb_u8_to_linear};
+use crate::palette::Palette;
+use crate::pixel_buffer::{BitmapBuffer, GreyBuffer, PixelBuffer, RgbaFloatBuffer};
+use std::fs;
+
 #[allow(dead_code)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Dithering {
     Atkinson,
     Burkes,
@@ -10,6 +16,35 @@ pub enum Dithering {
     SierraLite,
     Stucki,
     TwoRowSierra,
+    Bayer2,
+    Bayer4,
+    Bayer8,
+    ClusteredDot4,
+    ClusteredDot8,
+    BlueNoise,
+    Custom,
+}
+
+// the farthest a custom kernel can spread the error, every row below needs a row buffer
+const DITHER_KERNEL_MAX_X: i32 = 4;
+const DITHER_KERNEL_MAX_Y: i32 = 4;
+
+/// A diffusion matrix with `[x offset, y offset, weight]` entries, e.g. loaded from a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DitherKernel {
+    pub matrix: Vec<[i32; 3]>,
+    pub divisor: f32,
+}
+
+/// Everything the dithering stage needs besides the image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DitherOptions {
+    pub dither_type: Dithering,
+    /// The kernel for `Dithering::Custom`, without it the image is only thresholded.
+    pub custom_kernel: Option<DitherKernel>,
+    /// Scan every second row from right to left.
+    pub serpentine: bool,
+    pub linear: bool,
 }
 
 const DITHER_ATKINSON_MATRIX: [[i32; 3]; 6] = [
@@ -89,122 +124,419 @@ const DITHER_TWO_ROW_SIERRA_DIVISOR: f32 = 16.;
 const DITHER_SIERRA_LITE_MATRIX: [[i32; 3]; 3] = [[1, 0, 2], [-1, 1, 1], [0, 1, 1]];
 const DITHER_SIERRA_LITE_DIVISOR: f32 = 4.;
 
-fn is_in_bound(x: i32, y: i32, width: i32, height: i32) -> bool {
-    if x >= 0 && x < width && y >= 0 && y < height {
-        return true;
+// threshold maps for ordered dithering, every map contains each rank from 0 to size² - 1
+const DITHER_BAYER2_MAP: [u8; 4] = [0, 2, 3, 1];
+const DITHER_BAYER4_MAP: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+const DITHER_BAYER8_MAP: [u8; 64] = [
+    0, 32, 8, 40, 2, 34, 10, 42, 48, 16, 56, 24, 50, 18, 58, 26, 12, 44, 4, 36, 14, 46, 6, 38, 60,
+    28, 52, 20, 62, 30, 54, 22, 3, 35, 11, 43, 1, 33, 9, 41, 51, 19, 59, 27, 49, 17, 57, 25, 15,
+    47, 7, 39, 13, 45, 5, 37, 63, 31, 55, 23, 61, 29, 53, 21,
+];
+const DITHER_CLUSTERED_DOT4_MAP: [u8; 16] = [12, 5, 6, 13, 4, 0, 1, 7, 11, 3, 2, 8, 15, 10, 9, 14];
+// two dots in a 45 degree screen
+const DITHER_CLUSTERED_DOT8_MAP: [u8; 64] = [
+    6, 20, 44, 57, 58, 47, 23, 7, 21, 30, 49, 33, 34, 50, 31, 22, 45, 48, 24, 9, 10, 25, 51, 46,
+    56, 32, 8, 0, 1, 11, 35, 59, 63, 39, 15, 3, 2, 12, 36, 60, 42, 55, 27, 14, 13, 26, 52, 41, 18,
+    29, 54, 38, 37, 53, 28, 17, 5, 19, 43, 62, 61, 40, 16, 4,
+];
+// made with the void and cluster algorithm of Robert Ulichney, it tiles seamless
+const DITHER_BLUE_NOISE_MAP: [u8; 256] = [
+    234, 50, 188, 19, 58, 171, 121, 47, 163, 1, 247, 104, 22, 132, 14, 65, 209, 8, 118, 97, 240,
+    205, 23, 228, 138, 64, 123, 170, 72, 224, 99, 149, 85, 139, 229, 165, 78, 146, 111, 84, 176,
+    216, 30, 231, 153, 201, 42, 180, 25, 62, 195, 29, 43, 185, 7, 249, 41, 100, 191, 48, 87, 5,
+    128, 243, 221, 152, 101, 253, 130, 220, 59, 200, 156, 12, 136, 112, 255, 174, 69, 109, 46, 189,
+    0, 73, 172, 90, 142, 116, 80, 237, 210, 61, 147, 33, 206, 160, 81, 124, 217, 113, 208, 15, 241,
+    27, 168, 45, 178, 20, 193, 96, 225, 18, 242, 164, 60, 35, 157, 53, 181, 68, 223, 105, 125, 83,
+    236, 131, 55, 141, 197, 10, 227, 134, 246, 95, 126, 198, 148, 3, 244, 161, 71, 9, 182, 106, 40,
+    93, 179, 75, 192, 6, 218, 36, 91, 57, 202, 34, 215, 155, 233, 74, 252, 120, 150, 24, 110, 63,
+    166, 119, 232, 183, 133, 103, 49, 117, 31, 167, 16, 212, 51, 238, 207, 137, 254, 21, 76, 151,
+    13, 250, 190, 88, 203, 135, 102, 184, 82, 169, 38, 89, 187, 52, 204, 98, 173, 67, 129, 4, 222,
+    56, 230, 144, 2, 127, 226, 11, 154, 114, 239, 39, 219, 28, 235, 145, 175, 77, 196, 37, 248, 70,
+    107, 199, 66, 177, 17, 143, 115, 159, 86, 44, 108, 26, 122, 92, 158, 214, 140, 32, 245, 94,
+    213, 79, 194, 54, 211, 186, 251, 162,
+];
+
+/// The diffusion matrix with `[x offset, y offset, weight]` entries and its divisor.
+pub fn get_dither_matrix(dither_type: Dithering) -> Option<(&'static [[i32; 3]], f32)> {
+    match dither_type {
+        Dithering::Atkinson => Some((&DITHER_ATKINSON_MATRIX, DITHER_ATKINSON_DIVISOR)),
+        Dithering::Burkes => Some((&DITHER_BURKES_MATRIX, DITHER_BURKES_DIVISOR)),
+        Dithering::FloydSteinberg => {
+            Some((&DITHER_FLOYDSTEINBERG_MATRIX, DITHER_FLOYDSTEINBERG_DIVISOR))
+        }
+        Dithering::Jjn => Some((&DITHER_JJN_MATRIX, DITHER_JJN_DIVISOR)),
+        Dithering::Sierra => Some((&DITHER_SIERRA_MATRIX, DITHER_SIERRA_DIVISOR)),
+        Dithering::SierraLite => Some((&DITHER_SIERRA_LITE_MATRIX, DITHER_SIERRA_LITE_DIVISOR)),
+        Dithering::Stucki => Some((&DITHER_STUCKI_MATRIX, DITHER_STUCKI_DIVISOR)),
+        Dithering::TwoRowSierra => {
+            Some((&DITHER_TWO_ROW_SIERRA_MATRIX, DITHER_TWO_ROW_SIERRA_DIVISOR))
+        }
+        _ => None,
+    }
+}
+
+impl DitherKernel {
+    pub fn from_file(filename: &str) -> Result<DitherKernel, Img2AscError> {
+        DitherKernel::parse(&fs::read_to_string(filename)?)
+    }
+
+    /// Reads one `x y weight` entry per line, the numbers can also be separated by commas.
+    /// A line `divisor = <NUM>` sets the divisor, without it the sum of all weights is used.
+    /// Empty lines and everything after a `#` are ignored.
+    pub fn parse(text: &str) -> Result<DitherKernel, Img2AscError> {
+        let mut matrix: Vec<[i32; 3]> = Vec::new();
+        let mut divisor: Option<f32> = None;
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = |reason: &str| Img2AscError::InvalidKernel {
+                line: n + 1,
+                reason: reason.to_string(),
+            };
+
+            if let Some(value) = line.strip_prefix("divisor") {
+                let value = value.trim().trim_start_matches('=').trim();
+                let value = value
+                    .parse::<f32>()
+                    .map_err(|_| invalid("the divisor is no number"))?;
+                if value <= 0. {
+                    return Err(invalid("the divisor must be greater than 0"));
+                }
+                divisor = Some(value);
+                continue;
+            }
+
+            let numbers = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|n| !n.is_empty())
+                .map(|n| n.parse::<i32>())
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|_| invalid("expected 3 integers: x y weight"))?;
+
+            let [x, y, weight] = numbers[..] else {
+                return Err(invalid("expected 3 integers: x y weight"));
+            };
+            if y < 0 || (y == 0 && x <= 0) {
+                return Err(invalid(
+                    "the error can only go to pixels that are not dithered yet",
+                ));
+            }
+            if x.abs() > DITHER_KERNEL_MAX_X || y > DITHER_KERNEL_MAX_Y {
+                return Err(invalid(
+                    "the x offset must be within -4 and 4, the y offset within 0 and 4",
+                ));
+            }
+            matrix.push([x, y, weight]);
+        }
+
+        if matrix.is_empty() {
+            return Err(Img2AscError::InvalidKernel {
+                line: 0,
+                reason: "the kernel has no entries".to_string(),
+            });
+        }
+
+        let divisor = divisor.unwrap_or_else(|| matrix.iter().map(|p| p[2] as f32).sum());
+        if divisor <= 0. {
+            return Err(Img2AscError::InvalidKernel {
+                line: 0,
+                reason: "the sum of the weights must be greater than 0".to_string(),
+            });
+        }
+
+        Ok(DitherKernel { matrix, divisor })
     }
-    false
 }
 
-fn get_offset(x: u32, y: u32, width: u32, channel: usize) -> usize {
-    (width * y + x) as usize * channel
+impl DitherOptions {
+    pub fn new(dither_type: Dithering, linear: bool) -> DitherOptions {
+        DitherOptions {
+            dither_type,
+            custom_kernel: None,
+            serpentine: false,
+            linear,
+        }
+    }
+
+    fn matrix(&self) -> Option<(&[[i32; 3]], f32)> {
+        match self.dither_type {
+            Dithering::Custom => self
+                .custom_kernel
+                .as_ref()
+                .map(|kernel| (kernel.matrix.as_slice(), kernel.divisor)),
+            dither_type => get_dither_matrix(dither_type),
+        }
+    }
 }
 
-fn clamp_dither_value(input: u8, diff: u8, factor: u8) -> u8 {
-    let val = (input as f32 + diff as f32 * factor as f32).round() as i32;
-    if val < 0 {
-        0
-    } else if val > 255 {
-        255
+/// The threshold map for ordered dithering and its side length.
+pub fn get_threshold_map(dither_type: Dithering) -> Option<(&'static [u8], u32)> {
+    match dither_type {
+        Dithering::Bayer2 => Some((&DITHER_BAYER2_MAP, 2)),
+        Dithering::Bayer4 => Some((&DITHER_BAYER4_MAP, 4)),
+        Dithering::Bayer8 => Some((&DITHER_BAYER8_MAP, 8)),
+        Dithering::ClusteredDot4 => Some((&DITHER_CLUSTERED_DOT4_MAP, 4)),
+        Dithering::ClusteredDot8 => Some((&DITHER_CLUSTERED_DOT8_MAP, 8)),
+        Dithering::BlueNoise => Some((&DITHER_BLUE_NOISE_MAP, 16)),
+        _ => None,
+    }
+}
+
+// compare and calc the error in linear light, if wanted
+fn to_working(value: u8, linear: bool) -> f32 {
+    if linear {
+        srgb_u8_to_linear(value)
     } else {
-        val as u8
+        value as f32
     }
 }
 
-pub fn create_dither_image(
-    img_vec: &[u8],
+// evenly spaced levels of the sRGB encoded output as working values
+fn calc_working_levels(levels: usize, linear: bool) -> Vec<f32> {
+    (0..levels)
+        .map(|k| {
+            let value = k as f32 / (levels - 1) as f32;
+            if linear {
+                srgb_to_linear(value) * 255.
+            } else {
+                value * 255.
+            }
+        })
+        .collect()
+}
+
+// index of the highest level below the value, the level above always exists
+fn find_lower_level(levels: &[f32], value: f32) -> usize {
+    levels
+        .iter()
+        .rposition(|level| *level <= value)
+        .unwrap_or(0)
+        .min(levels.len() - 2)
+}
+
+fn find_nearest_level(levels: &[f32], value: f32) -> usize {
+    let k = find_lower_level(levels, value);
+    if value - levels[k] > levels[k + 1] - value {
+        k + 1
+    } else {
+        k
+    }
+}
+
+// the threshold depends only on the position, so the result is stable for animations
+fn ordered_dither(
+    image: &GreyBuffer,
+    map: &[u8],
+    size: u32,
+    linear: bool,
+    levels: &[f32],
+) -> Vec<usize> {
+    let mut result = Vec::with_capacity((image.width() * image.height()) as usize);
+    let ranks = (size * size) as f32;
+
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let value = to_working(image.get(x, y), linear);
+            let k = find_lower_level(levels, value);
+            let fraction = ((value - levels[k]) / (levels[k + 1] - levels[k])).clamp(0., 1.);
+
+            let rank = map[((y % size) * size + x % size) as usize];
+            let threshold = (rank as f32 + 0.5) / ranks;
+            result.push(if fraction > threshold { k + 1 } else { k });
+        }
+    }
+
+    result
+}
+
+// the quantizer gets the working values of a pixel and returns the index of the level
+// or color and its working values, every channel gets its own error
+fn diffuse_error<const N: usize>(
     width: u32,
     height: u32,
-    threshold: u8,
-    dither_type: Dithering,
-) -> Vec<u8> {
-    if dither_type == Dithering::NoDither {
-        return Vec::new();
-    }
+    options: &DitherOptions,
+    value_at: impl Fn(u32, u32) -> [f32; N],
+    quantize: impl Fn([f32; N]) -> (usize, [f32; N]),
+) -> Vec<usize> {
+    let mut result = vec![0; (width * height) as usize];
 
-    // calc channels from vector
-    let channels = img_vec.len() / (width * height) as usize;
+    let matrix = options.matrix();
 
-    // create result vector
-    let mut d_vec = img_vec.to_vec();
+    // signed error of the current row and the following rows the kernel reaches
+    let error_rows = matrix
+        .map(|(matrix, _)| matrix.iter().map(|p| p[1]).max().unwrap_or(0))
+        .unwrap_or(0) as usize
+        + 1;
+    let mut errors = vec![vec![[0f32; N]; width as usize]; error_rows];
 
-    // iter through image
     for y in 0..height {
-        for x in 0..width {
-            // calc offset of image vector
-            let offset = get_offset(x, y, width, channels);
-
-            // get color of pixel
-            let mut color: Vec<u8> = Vec::with_capacity(channels);
-            for n in 0..channels {
-                color.push(d_vec[offset + n]);
-            }
+        // on serpentine scan the odd rows go backwards with a mirrored kernel
+        let backwards = options.serpentine && y % 2 == 1;
 
-            // get dithering divisor
-            let grey_val: u8;
-            let diff_val: u8;
-            let divisor = match dither_type {
-                Dithering::Atkinson => DITHER_ATKINSON_DIVISOR,
-                Dithering::Burkes => DITHER_BURKES_DIVISOR,
-                Dithering::FloydSteinberg => DITHER_FLOYDSTEINBERG_DIVISOR,
-                Dithering::Jjn => DITHER_JJN_DIVISOR,
-                Dithering::Sierra => DITHER_SIERRA_DIVISOR,
-                Dithering::SierraLite => DITHER_SIERRA_LITE_DIVISOR,
-                Dithering::Stucki => DITHER_STUCKI_DIVISOR,
-                Dithering::TwoRowSierra => DITHER_TWO_ROW_SIERRA_DIVISOR,
-                _ => 1., // this never happens, look at start of function
-            };
+        for n in 0..width {
+            let x = if backwards { width - 1 - n } else { n };
 
-            if color[0] < threshold {
-                grey_val = 0;
-                diff_val = (color[0] as f32 / divisor).round() as u8;
-            } else {
-                grey_val = 255;
-                diff_val = ((color[0] as f32 - 255.) / divisor).round() as u8;
-            }
-            for n in 0..channels {
-                if n == 3 {
-                    d_vec[offset + 3] = color[3];
-                } else {
-                    d_vec[offset + n] = grey_val;
-                }
+            let mut value = value_at(x, y);
+            for (v, e) in value.iter_mut().zip(errors[0][x as usize]) {
+                *v += e;
             }
 
-            let dither_vec = match dither_type {
-                Dithering::Atkinson => Vec::from(DITHER_ATKINSON_MATRIX),
-                Dithering::Burkes => Vec::from(DITHER_BURKES_MATRIX),
-                Dithering::FloydSteinberg => Vec::from(DITHER_FLOYDSTEINBERG_MATRIX),
-                Dithering::Jjn => Vec::from(DITHER_JJN_MATRIX),
-                Dithering::Sierra => Vec::from(DITHER_SIERRA_MATRIX),
-                Dithering::SierraLite => Vec::from(DITHER_SIERRA_LITE_MATRIX),
-                Dithering::Stucki => Vec::from(DITHER_STUCKI_MATRIX),
-                Dithering::TwoRowSierra => Vec::from(DITHER_TWO_ROW_SIERRA_MATRIX),
-                _ => Vec::new(),
+            let (level, quantized) = quantize(value);
+            result[(y * width + x) as usize] = level;
+
+            let Some((matrix, divisor)) = matrix else {
+                continue;
             };
 
-            for p in dither_vec.iter() {
-                if is_in_bound(
-                    x as i32 + p[0],
-                    y as i32 + p[1],
-                    width as i32,
-                    height as i32,
-                ) {
-                    let offs = get_offset(
-                        (x as i32 + p[0]) as u32,
-                        (y as i32 + p[1]) as u32,
-                        width,
-                        channels,
-                    );
-                    let col = clamp_dither_value(img_vec[offs], diff_val, p[2] as u8);
-                    for n in 0..channels {
-                        if n != 3 {
-                            d_vec[offset + n] = col;
-                        }
+            for p in matrix.iter() {
+                let dx = if backwards { -p[0] } else { p[0] };
+                let nx = x as i32 + dx;
+                let ny = y as i32 + p[1];
+                if nx >= 0 && nx < width as i32 && ny < height as i32 {
+                    let target = &mut errors[p[1] as usize][nx as usize];
+                    for c in 0..N {
+                        target[c] += (value[c] - quantized[c]) * p[2] as f32 / divisor;
                     }
                 }
             }
         }
+
+        // the next row is now the current row
+        errors.rotate_left(1);
+        errors[error_rows - 1].fill([0.; N]);
     }
 
-    d_vec
+    result
+}
+
+/// Dithers to black and white, the threshold is only used by error diffusion and no dithering.
+pub fn create_dither_image(
+    image: &GreyBuffer,
+    threshold: u8,
+    options: &DitherOptions,
+) -> BitmapBuffer {
+    let levels = calc_working_levels(2, options.linear);
+
+    let result = if let Some((map, size)) = get_threshold_map(options.dither_type) {
+        ordered_dither(image, map, size, options.linear, &levels)
+    } else {
+        let threshold = to_working(threshold, options.linear);
+        diffuse_error(
+            image.width(),
+            image.height(),
+            options,
+            |x, y| [to_working(image.get(x, y), options.linear)],
+            |[value]| {
+                let level = (value >= threshold) as usize;
+                (level, [levels[level]])
+            },
+        )
+    };
+
+    let mut bitmap = BitmapBuffer::new(image.width(), image.height());
+    for (n, level) in result.iter().enumerate() {
+        bitmap.set(
+            n as u32 % image.width(),
+            n as u32 / image.width(),
+            *level == 1,
+        );
+    }
+    bitmap
+}
+
+/// Dithers to evenly spaced grey levels, e.g. the number of chars of an ascii type.
+pub fn create_level_dither_image(
+    image: &GreyBuffer,
+    level_count: usize,
+    options: &DitherOptions,
+) -> GreyBuffer {
+    let level_count = level_count.max(2);
+    let levels = calc_working_levels(level_count, options.linear);
+
+    let result = if let Some((map, size)) = get_threshold_map(options.dither_type) {
+        ordered_dither(image, map, size, options.linear, &levels)
+    } else {
+        diffuse_error(
+            image.width(),
+            image.height(),
+            options,
+            |x, y| [to_working(image.get(x, y), options.linear)],
+            |[value]| {
+                let level = find_nearest_level(&levels, value);
+                (level, [levels[level]])
+            },
+        )
+    };
+
+    let data = result
+        .iter()
+        .map(|level| (*level as f32 * 255. / (level_count - 1) as f32).round() as u8)
+        .collect();
+    GreyBuffer::from_raw(image.width(), image.height(), data).expect("one level for every pixel")
+}
+
+/// Dithers the colors of a sRGB image from 0.0 to 1.0 to the nearest colors of the palette,
+/// the error of every channel is diffused on its own. Ordered dithering moves every channel
+/// by the threshold, about the distance between the colors of the palette.
+pub fn create_palette_dither_image(
+    image: &RgbaFloatBuffer,
+    palette: &Palette,
+    options: &DitherOptions,
+) -> Vec<(u8, [u8; 3])> {
+    let entries = palette.entries();
+    let working: Vec<[f32; 3]> = entries
+        .iter()
+        .map(|(_, rgb)| rgb.map(|v| to_working(v, options.linear)))
+        .collect();
+    let srgb_at = |x: u32, y: u32| {
+        let pxl = image.get_pixel(x, y);
+        [pxl[0], pxl[1], pxl[2]]
+    };
+    let to_srgb = |value: [f32; 3]| {
+        value.map(|v| {
+            let v = (v / 255.).clamp(0., 1.);
+            if options.linear { linear_to_srgb(v) } else { v }
+        })
+    };
+
+    let result = if let Some((map, size)) = get_threshold_map(options.dither_type) {
+        let ranks = (size * size) as f32;
+        let spread = 1. / (entries.len() as f32).cbrt();
+        let mut result = Vec::with_capacity((image.width() * image.height()) as usize);
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                let rank = map[((y % size) * size + x % size) as usize];
+                let offset = ((rank as f32 + 0.5) / ranks - 0.5) * spread;
+                let (index, _) = palette.find_nearest_entry(srgb_at(x, y).map(|v| v + offset));
+                result.push(index);
+            }
+        }
+        result
+    } else {
+        diffuse_error(
+            image.width(),
+            image.height(),
+            options,
+            |x, y| {
+                srgb_at(x, y).map(|v| {
+                    let v = v.clamp(0., 1.);
+                    if options.linear {
+                        srgb_to_linear(v) * 255.
+                    } else {
+                        v * 255.
+                    }
+                })
+            },
+            |value| {
+                let (index, _) = palette.find_nearest_entry(to_srgb(value));
+                (index, working[index])
+            },
+        )
+    };
+
+    result.iter().map(|index| entries[*index]).collect()
 }
@@ -0,0 +1,210 @@
+use crate::ascii_image::ascii_ramp_char;
+use crate::pixel_buffer::{GreyBuffer, PixelBuffer};
+
+// direction of the edge: horizontal, rising, vertical and falling
+const EDGE_CHARS_ASCII: [char; 4] = ['-', '/', '|', '\\'];
+const EDGE_CHARS_BOX: [char; 4] = ['─', '╱', '│', '╲'];
+
+const SOBEL_X: [[i32; 3]; 3] = [[-1, 0, 1], [-2, 0, 2], [-1, 0, 1]];
+const SOBEL_Y: [[i32; 3]; 3] = [[-1, -2, -1], [0, 0, 0], [1, 2, 1]];
+const GAUSS_5X5: [[i32; 5]; 5] = [
+    [2, 4, 5, 4, 2],
+    [4, 9, 12, 9, 4],
+    [5, 12, 15, 12, 5],
+    [4, 9, 12, 9, 4],
+    [2, 4, 5, 4, 2],
+];
+const GAUSS_5X5_DIVISOR: i32 = 159;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeDetection {
+    Sobel,
+    Canny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeChars {
+    Ascii,
+    Box,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeOptions {
+    pub detection: EdgeDetection,
+    /// The gradient magnitude from 0 to 255 above that a pixel is an edge.
+    pub threshold: u8,
+    pub chars: EdgeChars,
+    /// Use the tonal ramp for all pixels without edge instead of spaces.
+    pub blend: bool,
+}
+
+struct Gradient {
+    magnitude: f32,
+    // 0 horizontal, 1 rising, 2 vertical, 3 falling
+    direction: usize,
+}
+
+fn get_pixel_clamped(image: &GreyBuffer, x: i32, y: i32) -> i32 {
+    let x = x.clamp(0, image.width() as i32 - 1) as u32;
+    let y = y.clamp(0, image.height() as i32 - 1) as u32;
+    image.get(x, y) as i32
+}
+
+fn create_blurred_image(image: &GreyBuffer) -> GreyBuffer {
+    let mut buffer = GreyBuffer::new(image.width(), image.height());
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let mut sum = 0;
+            for (dy, row) in GAUSS_5X5.iter().enumerate() {
+                for (dx, weight) in row.iter().enumerate() {
+                    let px = x as i32 + dx as i32 - 2;
+                    let py = y as i32 + dy as i32 - 2;
+                    sum += get_pixel_clamped(image, px, py) * weight;
+                }
+            }
+            buffer.set(x, y, (sum / GAUSS_5X5_DIVISOR) as u8);
+        }
+    }
+    buffer
+}
+
+// the magnitude is scaled to 0..255 for a step from black to white
+fn calc_gradients(image: &GreyBuffer) -> Vec<Gradient> {
+    let mut gradients = Vec::with_capacity((image.width() * image.height()) as usize);
+    for y in 0..image.height() as i32 {
+        for x in 0..image.width() as i32 {
+            let mut gx = 0;
+            let mut gy = 0;
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    let value = get_pixel_clamped(image, x + dx as i32 - 1, y + dy as i32 - 1);
+                    gx += value * SOBEL_X[dy][dx];
+                    gy += value * SOBEL_Y[dy][dx];
+                }
+            }
+
+            // the edge is perpendicular to the gradient, y goes down
+            let angle = (gy as f32).atan2(gx as f32).to_degrees().rem_euclid(180.);
+            let direction = match angle {
+                a if !(22.5..157.5).contains(&a) => 2,
+                a if a < 67.5 => 1,
+                a if a < 112.5 => 0,
+                _ => 3,
+            };
+            gradients.push(Gradient {
+                magnitude: ((gx * gx + gy * gy) as f32).sqrt() / 4.,
+                direction,
+            });
+        }
+    }
+    gradients
+}
+
+fn detect_sobel(gradients: &[Gradient], threshold: f32) -> Vec<bool> {
+    gradients.iter().map(|g| g.magnitude >= threshold).collect()
+}
+
+// non-maximum suppression along the gradient and hysteresis with a low threshold of the half
+fn detect_canny(gradients: &[Gradient], width: u32, height: u32, threshold: f32) -> Vec<bool> {
+    let (width, height) = (width as i32, height as i32);
+    let magnitude = |x: i32, y: i32| -> f32 {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            0.
+        } else {
+            gradients[(y * width + x) as usize].magnitude
+        }
+    };
+
+    let mut thin = vec![0.; gradients.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let g = &gradients[(y * width + x) as usize];
+            // the neighbours across the edge
+            let (dx, dy) = match g.direction {
+                0 => (0, 1),
+                1 => (1, 1),
+                2 => (1, 0),
+                _ => (-1, 1),
+            };
+            // equal neighbours keep only the first pixel, a sharp step is one line
+            if g.magnitude > magnitude(x + dx, y + dy) && g.magnitude >= magnitude(x - dx, y - dy) {
+                thin[(y * width + x) as usize] = g.magnitude;
+            }
+        }
+    }
+
+    let low = threshold / 2.;
+    let mut edges = vec![false; gradients.len()];
+    let mut stack: Vec<(i32, i32)> = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if thin[(y * width + x) as usize] >= threshold {
+                stack.push((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = stack.pop() {
+        let offset = (y * width + x) as usize;
+        if edges[offset] {
+            continue;
+        }
+        edges[offset] = true;
+        for ny in y - 1..=y + 1 {
+            for nx in x - 1..=x + 1 {
+                if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                    continue;
+                }
+                let n = (ny * width + nx) as usize;
+                if !edges[n] && thin[n] >= low {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+    edges
+}
+
+/// Draws the edges of the image with chars for their direction, one char per pixel.
+/// All other pixels are spaces or, with `blend`, the chars of the ramp for the `tones`.
+pub fn create_edge_image(
+    image: &GreyBuffer,
+    tones: &GreyBuffer,
+    ramp: &[char],
+    options: &EdgeOptions,
+) -> String {
+    let threshold = options.threshold.max(1) as f32;
+    let chars = match options.chars {
+        EdgeChars::Ascii => &EDGE_CHARS_ASCII,
+        EdgeChars::Box => &EDGE_CHARS_BOX,
+    };
+
+    let (gradients, edges) = match options.detection {
+        EdgeDetection::Sobel => {
+            let gradients = calc_gradients(image);
+            let edges = detect_sobel(&gradients, threshold);
+            (gradients, edges)
+        }
+        EdgeDetection::Canny => {
+            let gradients = calc_gradients(&create_blurred_image(image));
+            let edges = detect_canny(&gradients, image.width(), image.height(), threshold);
+            (gradients, edges)
+        }
+    };
+
+    let mut edge_text = String::new();
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let offset = (y * image.width() + x) as usize;
+            if edges[offset] {
+                edge_text.push(chars[gradients[offset].direction]);
+            } else if options.blend && ramp.len() > 1 {
+                edge_text.push(ascii_ramp_char(tones.get(x, y), ramp));
+            } else {
+                edge_text.push(' ');
+            }
+        }
+        edge_text.push('\n');
+    }
+
+    edge_text
+}
@@ -0,0 +1,89 @@
+use image::ImageError;
+use image::error::ImageFormatHint;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Img2AscError {
+    Io(std::io::Error),
+    Decode(ImageError),
+    Encode(ImageError),
+    UnsupportedFormat(String),
+    InvalidOption { option: String, value: String },
+    MissingArgument(String),
+    InvalidDimension { width: u32, height: u32 },
+    InvalidKernel { line: usize, reason: String },
+    InvalidFont(String),
+    InvalidPalette(String),
+}
+
+impl Img2AscError {
+    /// The exit code of the cli for this error, see the help text.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Img2AscError::Io(_) | Img2AscError::Encode(_) => 1,
+            Img2AscError::UnsupportedFormat(_) => 2,
+            Img2AscError::Decode(_) => 3,
+            Img2AscError::InvalidOption { .. } => 4,
+            Img2AscError::MissingArgument(_) => 5,
+            Img2AscError::InvalidDimension { .. } => 6,
+            Img2AscError::InvalidKernel { .. } => 7,
+            Img2AscError::InvalidFont(_) => 8,
+            Img2AscError::InvalidPalette(_) => 9,
+        }
+    }
+}
+
+impl fmt::Display for Img2AscError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Img2AscError::Io(err) => write!(f, "{}", err),
+            Img2AscError::Decode(err) => write!(f, "can't decode image: {}", err),
+            Img2AscError::Encode(err) => write!(f, "can't encode image: {}", err),
+            Img2AscError::UnsupportedFormat(format) => {
+                write!(f, "unsupported image format: {}", format)
+            }
+            Img2AscError::InvalidOption { option, value } => {
+                write!(f, "invalid value {} for option {}", value, option)
+            }
+            Img2AscError::MissingArgument(arg) => write!(f, "missing argument: {}", arg),
+            Img2AscError::InvalidDimension { width, height } => {
+                write!(f, "invalid image size {}x{}", width, height)
+            }
+            Img2AscError::InvalidKernel { line, reason } => {
+                write!(f, "invalid dither kernel in line {}: {}", line, reason)
+            }
+            Img2AscError::InvalidFont(reason) => write!(f, "invalid font: {}", reason),
+            Img2AscError::InvalidPalette(reason) => write!(f, "invalid palette: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Img2AscError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Img2AscError::Io(err) => Some(err),
+            Img2AscError::Decode(err) | Img2AscError::Encode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Img2AscError {
+    fn from(err: std::io::Error) -> Self {
+        Img2AscError::Io(err)
+    }
+}
+
+impl From<ImageError> for Img2AscError {
+    fn from(err: ImageError) -> Self {
+        match err {
+            ImageError::IoError(err) => Img2AscError::Io(err),
+            err @ ImageError::Encoding(_) => Img2AscError::Encode(err),
+            ImageError::Unsupported(err) => match err.format_hint() {
+                ImageFormatHint::Unknown => Img2AscError::UnsupportedFormat(err.to_string()),
+                hint => Img2AscError::UnsupportedFormat(hint.to_string()),
+            },
+            err => Img2AscError::Decode(err),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::pixel_buffer::{PixelBuffer, RgbaFloatBuffer};
+
+/// Decodes a sRGB value from 0.0 to 1.0 into linear light.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear light value from 0.0 to 1.0 into sRGB.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Decodes a 8 bit sRGB value into linear light, also from 0 to 255 but as float.
+pub fn srgb_u8_to_linear(value: u8) -> f32 {
+    srgb_to_linear(value as f32 / 255.) * 255.
+}
+
+/// The CIELAB lightness L* from 0.0 to 100.0 of a relative luminance from 0.0 to 1.0.
+pub fn lightness(luminance: f32) -> f32 {
+    if luminance <= 216. / 24389. {
+        luminance * 24389. / 27.
+    } else {
+        116. * luminance.cbrt() - 16.
+    }
+}
+
+fn convert_image(image: &RgbaFloatBuffer, convert: fn(f32) -> f32) -> RgbaFloatBuffer {
+    let mut buffer = RgbaFloatBuffer::new(image.width(), image.height());
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let pxl = image.get_pixel(x, y);
+            // alpha is always linear
+            buffer.put_pixel(
+                x,
+                y,
+                [convert(pxl[0]), convert(pxl[1]), convert(pxl[2]), pxl[3]],
+            );
+        }
+    }
+    buffer
+}
+
+pub fn create_linear_image(image: &RgbaFloatBuffer) -> RgbaFloatBuffer {
+    convert_image(image, srgb_to_linear)
+}
+
+pub fn create_srgb_image(image: &RgbaFloatBuffer) -> RgbaFloatBuffer {
+    convert_image(image, linear_to_srgb)
+}
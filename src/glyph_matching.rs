@@ -0,0 +1,124 @@
+use crate::ascii_image::{CellFit, CellGrid};
+use crate::bitmap_font::BitmapFont;
+use crate::pixel_buffer::{GreyBuffer, PixelBuffer};
+
+// the constants of the SSIM paper for 8 bit values
+const SSIM_C1: f32 = (0.01 * 255.) * (0.01 * 255.);
+const SSIM_C2: f32 = (0.03 * 255.) * (0.03 * 255.);
+
+/// How a cell of the image is compared with the glyphs of the font.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlyphMatch {
+    /// Mean squared error, keeps the brightness of the cells.
+    Mse,
+    /// Structural similarity of the whole cell, prefers glyphs with the same edges.
+    Ssim,
+}
+
+struct Glyph {
+    c: char,
+    pixels: Vec<f32>,
+    mean: f32,
+    variance: f32,
+}
+
+// the glyphs are black ink on white paper, like the char ramps
+fn create_glyph(font: &BitmapFont, c: char) -> Glyph {
+    let mut pixels = Vec::with_capacity((font.width() * font.height()) as usize);
+    for y in 0..font.height() {
+        for x in 0..font.width() {
+            pixels.push(if font.get(c, x, y) { 0. } else { 255. });
+        }
+    }
+    let (mean, variance) = calc_mean_variance(&pixels);
+    Glyph {
+        c,
+        pixels,
+        mean,
+        variance,
+    }
+}
+
+fn calc_mean_variance(pixels: &[f32]) -> (f32, f32) {
+    let count = pixels.len() as f32;
+    let mean = pixels.iter().sum::<f32>() / count;
+    let variance = pixels.iter().map(|p| (p - mean) * (p - mean)).sum::<f32>() / count;
+    (mean, variance)
+}
+
+fn match_mse(patch: &[f32], glyph: &Glyph) -> f32 {
+    let sum: f32 = patch
+        .iter()
+        .zip(&glyph.pixels)
+        .map(|(p, g)| (p - g) * (p - g))
+        .sum();
+    -sum / patch.len() as f32
+}
+
+fn match_ssim(patch: &[f32], mean: f32, variance: f32, glyph: &Glyph) -> f32 {
+    let covariance = patch
+        .iter()
+        .zip(&glyph.pixels)
+        .map(|(p, g)| (p - mean) * (g - glyph.mean))
+        .sum::<f32>()
+        / patch.len() as f32;
+
+    ((2. * mean * glyph.mean + SSIM_C1) * (2. * covariance + SSIM_C2))
+        / ((mean * mean + glyph.mean * glyph.mean + SSIM_C1)
+            * (variance + glyph.variance + SSIM_C2))
+}
+
+/// The printable ASCII chars of the font, used if no chars are given.
+pub fn get_font_chars(font: &BitmapFont) -> Vec<char> {
+    (' '..='~').filter(|c| font.has_glyph(*c)).collect()
+}
+
+/// Picks for every cell of the size of a glyph the char that looks most like it.
+/// Chars without glyph are skipped, except the space that never has ink. Pixels
+/// of partial cells outside of the image are white.
+pub fn create_glyph_image(
+    image: &GreyBuffer,
+    font: &BitmapFont,
+    chars: &[char],
+    glyph_match: GlyphMatch,
+    fit: CellFit,
+) -> String {
+    let glyphs: Vec<Glyph> = chars
+        .iter()
+        .filter(|c| font.has_glyph(**c) || **c == ' ')
+        .map(|c| create_glyph(font, *c))
+        .collect();
+
+    let (cell_w, cell_h) = (font.width(), font.height());
+    let grid = CellGrid::new(image.width(), image.height(), cell_w, cell_h, fit);
+    let mut patch: Vec<f32> = Vec::with_capacity((cell_w * cell_h) as usize);
+    let mut glyph_text = String::new();
+
+    for row in 0..grid.rows {
+        for col in 0..grid.cols {
+            patch.clear();
+            for y in 0..cell_h {
+                for x in 0..cell_w {
+                    let pixel = grid.pixel(col, row, x, y).map(|(px, py)| image.get(px, py));
+                    patch.push(pixel.unwrap_or(255) as f32);
+                }
+            }
+            let (mean, variance) = calc_mean_variance(&patch);
+
+            let mut best = (' ', f32::MIN);
+            for glyph in glyphs.iter() {
+                let score = match glyph_match {
+                    GlyphMatch::Mse => match_mse(&patch, glyph),
+                    GlyphMatch::Ssim => match_ssim(&patch, mean, variance, glyph),
+                };
+                if score > best.1 {
+                    best = (glyph.c, score);
+                }
+            }
+            glyph_text.push(best.0);
+        }
+        glyph_text.push('\n');
+    }
+
+    glyph_text
+}
@@ -1,6 +1,7 @@
-use std::cmp::{max, min};
+use crate::gamma::linear_to_srgb;
+use crate::pixel_buffer::{GreyBuffer, PixelBuffer, RgbaFloatBuffer, quantize_u8};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GreyScale {
     Average,
     Desaturate,
@@ -8,67 +9,69 @@ pub enum GreyScale {
     Maximum,
 }
 
-fn greyscale_average(red: u8, green: u8, blue: u8) -> u8 {
-    (red + green + blue) / 3
+fn greyscale_average(red: f32, green: f32, blue: f32) -> f32 {
+    (red + green + blue) / 3.
 }
 
-fn greyscale_desaturate(red: u8, green: u8, blue: u8) -> u8 {
-    let grey_max = max(max(red, green), blue);
-    let grey_min = min(min(red, green), blue);
-    (grey_max + grey_min) / 2
+fn greyscale_desaturate(red: f32, green: f32, blue: f32) -> f32 {
+    let grey_max = red.max(green).max(blue);
+    let grey_min = red.min(green).min(blue);
+    (grey_max + grey_min) / 2.
 }
 
-fn greyscale_luminance(red: u8, green: u8, blue: u8) -> u8 {
-    (red as f32 * 0.2989 + green as f32 * 0.587 + blue as f32 * 0.114).round() as u8
+fn greyscale_luminance(red: f32, green: f32, blue: f32) -> f32 {
+    red * 0.2989 + green * 0.587 + blue * 0.114
 }
 
-fn greyscale_maximum(red: u8, green: u8, blue: u8) -> u8 {
-    max(max(red, green), blue)
+// the weights of Rec. 709 for linear light
+fn greyscale_relative_luminance(red: f32, green: f32, blue: f32) -> f32 {
+    red * 0.2126 + green * 0.7152 + blue * 0.0722
+}
+
+fn greyscale_maximum(red: f32, green: f32, blue: f32) -> f32 {
+    red.max(green).max(blue)
 }
 
 pub fn create_greyscale_image(
-    img_vec: &[u8],
-    width: u32,
-    height: u32,
+    image: &RgbaFloatBuffer,
     greyscale: GreyScale,
     invert: bool,
     alpha_threshold: u8,
-) -> Vec<u8> {
-    // calc channels from vector size
-    let channels = img_vec.len() / (width * height) as usize;
+    linear: bool,
+) -> GreyBuffer {
+    let mut grey_buffer = GreyBuffer::new(image.width(), image.height());
 
-    let mut grey_vec: Vec<u8> = Vec::with_capacity((width * height) as usize * channels);
-
-    for y in 0..height {
-        for x in 0..width {
-            // calc where in the vector the correct pixel is
-            let offset = channels * (y * width + x) as usize;
-            let mut pxl: Vec<u8> = Vec::with_capacity(channels);
-            for c in 0..channels {
-                pxl.push(img_vec[offset + c]);
-            }
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let pxl = image.get_pixel(x, y);
 
-            let grey: u8;
-            if channels == 4 && pxl[3] < alpha_threshold {
-                grey = 0;
+            let mut grey: f32;
+            if quantize_u8(pxl[3]) < alpha_threshold {
+                grey = 0.;
             } else {
                 match greyscale {
                     GreyScale::Average => grey = greyscale_average(pxl[0], pxl[1], pxl[2]),
                     GreyScale::Desaturate => grey = greyscale_desaturate(pxl[0], pxl[1], pxl[2]),
+                    GreyScale::Luminance if linear => {
+                        grey = greyscale_relative_luminance(pxl[0], pxl[1], pxl[2])
+                    }
                     GreyScale::Luminance => grey = greyscale_luminance(pxl[0], pxl[1], pxl[2]),
                     GreyScale::Maximum => grey = greyscale_maximum(pxl[0], pxl[1], pxl[2]),
                 }
             }
 
-            for _ in pxl {
-                if invert {
-                    grey_vec.push(255 - grey);
-                } else {
-                    grey_vec.push(grey);
-                }
+            // the char selection works with sRGB values
+            if linear {
+                grey = linear_to_srgb(grey);
+            }
+
+            if invert {
+                grey_buffer.set(x, y, 255 - quantize_u8(grey));
+            } else {
+                grey_buffer.set(x, y, quantize_u8(grey));
             }
         }
     }
 
-    grey_vec
+    grey_buffer
 }
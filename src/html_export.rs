@@ -0,0 +1,135 @@
+use crate::coloring::{CellColor, create_color_runs};
+
+/// The font and background of the HTML and SVG output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    /// A CSS font family like `'DejaVu Sans Mono', monospace`.
+    pub font_family: String,
+    /// The font size in pixels.
+    pub font_size: f32,
+    /// The line height as multiple of the font size.
+    pub line_height: f32,
+    /// The text is black on light and white on dark backgrounds.
+    pub background: [u8; 3],
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_family: "monospace".to_string(),
+            font_size: 12.,
+            line_height: 1.,
+            background: [255; 3],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlOptions {
+    /// Only the `<pre>` to paste into other pages, instead of a whole document.
+    pub fragment: bool,
+    pub title: String,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            fragment: false,
+            title: "img2asc".to_string(),
+        }
+    }
+}
+
+pub(crate) fn hex_color(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+// the perceived brightness of the background decides between black and white text
+pub(crate) fn contrast_color(background: [u8; 3]) -> [u8; 3] {
+    let [r, g, b] = background.map(|v| v as u32);
+    if r * 299 + g * 587 + b * 114 < 128_000 {
+        [255; 3]
+    } else {
+        [0; 3]
+    }
+}
+
+/// Replaces the chars with a meaning in HTML and XML by entities.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn span_style(color: &CellColor) -> String {
+    match color.bg {
+        Some(bg) => format!(
+            "color:{};background-color:{}",
+            hex_color(color.fg.rgb),
+            hex_color(bg.rgb)
+        ),
+        None => format!("color:{}", hex_color(color.fg.rgb)),
+    }
+}
+
+// one span for every run of chars with the same colors
+fn create_html_lines(text: &str, colors: &[CellColor]) -> String {
+    let mut html_text = String::new();
+    for runs in create_color_runs(text, colors) {
+        for run in runs {
+            match run.color {
+                Some(color) => html_text.push_str(&format!(
+                    "<span style=\"{}\">{}</span>",
+                    span_style(&color),
+                    escape_html(&run.text)
+                )),
+                None => html_text.push_str(&escape_html(&run.text)),
+            }
+        }
+        html_text.push('\n');
+    }
+    html_text
+}
+
+/// Writes the ascii image into a `<pre>`, with the colors of the cells as `<span>` tags.
+/// Without `fragment` it is a whole HTML document.
+pub fn create_html(
+    text: &str,
+    colors: Option<&[CellColor]>,
+    style: &TextStyle,
+    options: &HtmlOptions,
+) -> String {
+    let lines = match colors {
+        Some(colors) => create_html_lines(text, colors),
+        None => escape_html(text),
+    };
+    let pre = format!(
+        "<pre style=\"font-family:{};font-size:{}px;line-height:{};color:{};background-color:{}\">{}</pre>\n",
+        escape_html(&style.font_family),
+        style.font_size,
+        style.line_height,
+        hex_color(contrast_color(style.background)),
+        hex_color(style.background),
+        lines
+    );
+
+    if options.fragment {
+        return pre;
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body style=\"background-color:{}\">\n{}</body>\n</html>\n",
+        escape_html(&options.title),
+        hex_color(style.background),
+        pre
+    )
+}
@@ -0,0 +1,452 @@
+// Image To Ascii converter for the console
+//
+// Copyright (c) 2025 Markus Müller
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+pub mod ansi_art;
+pub mod ascii_image;
+pub mod bitmap_font;
+pub mod coloring;
+pub mod dithering;
+pub mod edge_detection;
+pub mod error;
+pub mod gamma;
+pub mod glyph_matching;
+pub mod greyscaling;
+pub mod html_export;
+pub mod normalizing;
+pub mod palette;
+pub mod pixel_buffer;
+pub mod png_export;
+pub mod resizing;
+pub mod svg_export;
+
+use crate::ascii_image::*;
+use crate::bitmap_font::BitmapFont;
+use crate::coloring::*;
+use crate::dithering::*;
+use crate::edge_detection::*;
+use crate::gamma::*;
+use crate::glyph_matching::*;
+use crate::greyscaling::*;
+use crate::normalizing::*;
+use crate::palette::*;
+use crate::pixel_buffer::*;
+use crate::resizing::*;
+use image::{DynamicImage, ImageReader};
+
+pub use crate::error::Img2AscError;
+
+/// All settings of the conversion pipeline, the defaults are the same as in the cli.
+#[derive(Debug, Clone)]
+pub struct Converter {
+    pub threshold: u8,
+    pub alpha_threshold: u8,
+    pub invert: bool,
+    /// The dithering of the black and white types dot, braille, quadrant and sextant.
+    pub dither: Dithering,
+    /// Dither the grey levels of the char ramps and the palette colors with `dither` too.
+    pub dither_levels: bool,
+    pub grey_scale: GreyScale,
+    pub ascii_type: AsciiType,
+    pub ascii_width: u16,
+    pub ascii_height: u16,
+    pub resize_opt: ResizeType,
+    /// Resize, greyscale and dithering in linear light instead of sRGB values.
+    pub linear: bool,
+    /// Error diffusion with every second row from right to left.
+    pub serpentine: bool,
+    /// The kernel for `Dithering::Custom`.
+    pub dither_kernel: Option<DitherKernel>,
+    /// The char ramp for `AsciiType::Custom`, from black to white.
+    pub charset: Vec<char>,
+    /// Sorts the ramp by the glyphs of `font`, unless the order is `Given`. The types
+    /// without ramp and the glyph candidates ignore it.
+    pub ramp_order: RampOrder,
+    pub font: Option<BitmapFont>,
+    /// The comparison of cells and glyphs for `AsciiType::Glyph`.
+    pub glyph_match: GlyphMatch,
+    pub edge_detection: EdgeDetection,
+    /// The gradient magnitude from 0 to 255 above that a pixel is an edge.
+    pub edge_threshold: u8,
+    pub edge_chars: EdgeChars,
+    /// Fill the cells without edge with the tonal ramp for `AsciiType::Edge`.
+    pub edge_blend: bool,
+    /// Pad and crop keep the aspect ratio of the image, stretch fills whole cells.
+    pub cell_fit: CellFit,
+    /// The ANSI colors of the chars, half blocks are always colored.
+    pub color: ColorMode,
+    /// Color the background of the chars instead of the chars.
+    pub color_background: bool,
+    /// The 16 colors of the terminal for `ColorMode::Ansi16`.
+    pub palette: [[u8; 3]; 16],
+    /// The color space for the nearest palette color.
+    pub color_space: ColorSpace,
+}
+
+/// The result of a conversion, with the intermediate images of every stage.
+#[derive(Debug)]
+pub struct Conversion {
+    pub cols: u32,
+    pub rows: u32,
+    pub width: u32,
+    pub height: u32,
+    pub scaled: RgbaFloatBuffer,
+    pub grey: GreyBuffer,
+    pub dither: Option<GreyBuffer>,
+    /// The chars, one line per row.
+    pub text: String,
+    /// The colors of the chars row by row, if the output is colored.
+    pub colors: Option<Vec<CellColor>>,
+    /// The text with the ANSI colors, the same as `text` without colors.
+    pub ansi: String,
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Self {
+            threshold: 128,
+            alpha_threshold: 30,
+            invert: false,
+            dither: Dithering::TwoRowSierra,
+            dither_levels: false,
+            grey_scale: GreyScale::Luminance,
+            ascii_type: AsciiType::Simple,
+            ascii_width: 80,
+            ascii_height: 0,
+            resize_opt: ResizeType::Bilinear,
+            linear: true,
+            serpentine: false,
+            dither_kernel: None,
+            charset: Vec::new(),
+            ramp_order: RampOrder::Given,
+            font: None,
+            glyph_match: GlyphMatch::Mse,
+            edge_detection: EdgeDetection::Sobel,
+            edge_threshold: 64,
+            edge_chars: EdgeChars::Ascii,
+            edge_blend: false,
+            cell_fit: CellFit::Stretch,
+            color: ColorMode::None,
+            color_background: false,
+            palette: PALETTE_XTERM,
+            color_space: ColorSpace::Oklab,
+        }
+    }
+}
+
+impl Converter {
+    pub fn new() -> Converter {
+        Converter::default()
+    }
+
+    /// Size of the ascii image in cols and rows for an image of the given size, the same
+    /// as the size of the conversion.
+    pub fn ascii_size(&self, img_width: u32, img_height: u32) -> Result<(u32, u32), Img2AscError> {
+        let grid = self.calc_grid(img_width, img_height, self.cell_size()?)?;
+        Ok((grid.cols, grid.rows))
+    }
+
+    /// The pixels of one char, the glyph size of the font for `AsciiType::Glyph`.
+    pub fn cell_size(&self) -> Result<(u32, u32), Img2AscError> {
+        match (self.ascii_type, &self.font) {
+            (AsciiType::Glyph, Some(font)) => Ok((font.width(), font.height())),
+            (AsciiType::Glyph, None) => Err(Img2AscError::MissingArgument("font".to_string())),
+            (ascii_type, _) => Ok(get_cell_size(ascii_type)),
+        }
+    }
+
+    // the cells over the resized image, pad and crop round the free side to whole
+    // cells only after resizing
+    fn calc_grid(
+        &self,
+        img_width: u32,
+        img_height: u32,
+        (cell_w, cell_h): (u32, u32),
+    ) -> Result<CellGrid, Img2AscError> {
+        if img_width == 0 || img_height == 0 {
+            return Err(Img2AscError::InvalidDimension {
+                width: img_width,
+                height: img_height,
+            });
+        }
+
+        let (cols, rows) =
+            calc_image_size(img_width, img_height, self.ascii_width, self.ascii_height);
+        let (width, height) =
+            self.calc_pixel_size(img_width, img_height, cols, rows, cell_w, cell_h);
+        let grid = CellGrid::new(width, height, cell_w, cell_h, self.cell_fit);
+        if grid.cols == 0 || grid.rows == 0 {
+            return Err(Img2AscError::InvalidDimension {
+                width: grid.cols,
+                height: grid.rows,
+            });
+        }
+        Ok(grid)
+    }
+
+    // the char levels and palette colors are only dithered on request
+    fn dithers_levels(&self) -> bool {
+        self.dither_levels && self.dither != Dithering::NoDither
+    }
+
+    // with a free width or height the size in pixels follows the image without rounding
+    // to whole cells, a char is half as wide as high
+    fn calc_pixel_size(
+        &self,
+        img_width: u32,
+        img_height: u32,
+        cols: u32,
+        rows: u32,
+        cell_w: u32,
+        cell_h: u32,
+    ) -> (u32, u32) {
+        let ar = img_height as f32 / img_width as f32;
+        let cell_ar = ASCII_X_DOTS as f32 / ASCII_Y_DOTS as f32;
+        match self.cell_fit {
+            CellFit::Stretch => (cols * cell_w, rows * cell_h),
+            _ if self.ascii_width > 0 && self.ascii_height > 0 => (cols * cell_w, rows * cell_h),
+            _ if self.ascii_height > 0 => {
+                let width = rows as f32 * cell_w as f32 / (ar * cell_ar);
+                ((width.round() as u32).max(1), rows * cell_h)
+            }
+            _ => {
+                let height = cols as f32 * cell_h as f32 * ar * cell_ar;
+                (cols * cell_w, (height.round() as u32).max(1))
+            }
+        }
+    }
+
+    /// Runs the whole pipeline: normalize, resize, greyscale, dither and char mapping.
+    pub fn convert(&self, image: &DynamicImage) -> Result<Conversion, Img2AscError> {
+        if self.dither == Dithering::Custom && self.dither_kernel.is_none() {
+            return Err(Img2AscError::InvalidKernel {
+                line: 0,
+                reason: "the custom dithering has no kernel".to_string(),
+            });
+        }
+
+        // braille has no ramp, glyph uses the ramp only as the chars to choose from
+        let uses_ramp =
+            get_ascii_ramp(self.ascii_type).is_some() || self.ascii_type == AsciiType::Custom;
+        let ramp: &[char] = get_ascii_ramp(self.ascii_type).unwrap_or(&self.charset);
+        let ramp = match (self.ramp_order, &self.font) {
+            (RampOrder::Given, _) => ramp.to_vec(),
+            _ if !uses_ramp => ramp.to_vec(),
+            (order, Some(font)) => create_font_ramp(ramp, font, order == RampOrder::Even),
+            (_, None) => {
+                return Err(Img2AscError::MissingArgument(
+                    "font for the ramp order".to_string(),
+                ));
+            }
+        };
+        if uses_ramp && ramp.len() < 2 {
+            return Err(Img2AscError::InvalidOption {
+                option: "charset".to_string(),
+                value: ramp.iter().collect(),
+            });
+        }
+
+        // some types use more pixels per char
+        let grid = self.calc_grid(image.width(), image.height(), self.cell_size()?)?;
+        let (width, height) = grid.size();
+
+        let mut normalized = create_normalized_image(image);
+        if self.linear {
+            normalized = create_linear_image(&normalized);
+        }
+        let resized = create_resized_image(&normalized, width, height, self.resize_opt);
+
+        let grey = create_greyscale_image(
+            &resized,
+            self.grey_scale,
+            self.invert,
+            self.alpha_threshold,
+            self.linear,
+        );
+
+        let colors = match self.color {
+            _ if self.ascii_type == AsciiType::HalfBlock => Some(create_half_block_colors(
+                &resized,
+                &grid,
+                self.alpha_threshold,
+                self.linear,
+                self.invert,
+            )),
+            ColorMode::None => None,
+            _ => Some(create_cell_colors(
+                &resized,
+                &grid,
+                self.alpha_threshold,
+                self.linear,
+                self.color_background,
+                self.invert,
+            )),
+        };
+
+        let dither_options = DitherOptions {
+            dither_type: self.dither,
+            custom_kernel: self.dither_kernel.clone(),
+            serpentine: self.serpentine,
+            linear: self.linear,
+        };
+
+        // half blocks dither both colors, the background mode only the background
+        let palette = match self.color {
+            ColorMode::Ansi256 => Some(Palette::xterm256(self.color_space)),
+            ColorMode::Ansi16 => Some(Palette::ansi16(&self.palette, self.color_space)),
+            _ => None,
+        };
+        let colors = match (colors, palette) {
+            (Some(colors), Some(palette)) if self.dithers_levels() => {
+                let half_block = self.ascii_type == AsciiType::HalfBlock;
+                Some(create_palette_dither_colors(
+                    &colors,
+                    grid.cols,
+                    &palette,
+                    &dither_options,
+                    half_block || !self.color_background,
+                    half_block || self.color_background,
+                ))
+            }
+            (Some(colors), Some(palette)) => Some(create_palette_colors(&colors, &palette)),
+            (colors, _) => colors,
+        };
+
+        // the resized image is always returned sRGB encoded
+        let scaled = if self.linear {
+            create_srgb_image(&resized)
+        } else {
+            resized
+        };
+
+        // dot and the sub-cell types use black and white, all others the levels of their char set
+        let bitmap = if matches!(
+            self.ascii_type,
+            AsciiType::Dot | AsciiType::Braille | AsciiType::Quadrant | AsciiType::Sextant
+        ) {
+            Some(create_dither_image(&grey, self.threshold, &dither_options))
+        } else {
+            None
+        };
+
+        let dither = match &bitmap {
+            Some(bitmap) => Some(bitmap.to_grey()),
+            None if self.dithers_levels() && uses_ramp => Some(create_level_dither_image(
+                &grey,
+                ramp.len(),
+                &dither_options,
+            )),
+            None => None,
+        };
+
+        let text = match (&bitmap, &self.font) {
+            (Some(bitmap), _) if self.ascii_type == AsciiType::Braille => {
+                ascii_type_braille(bitmap, self.cell_fit)
+            }
+            (Some(bitmap), _) if self.ascii_type == AsciiType::Quadrant => {
+                ascii_type_quadrant(bitmap, self.cell_fit)
+            }
+            (Some(bitmap), _) if self.ascii_type == AsciiType::Sextant => {
+                ascii_type_sextant(bitmap, self.cell_fit)
+            }
+            (_, Some(font)) if self.ascii_type == AsciiType::Glyph => {
+                let chars = if ramp.is_empty() {
+                    get_font_chars(font)
+                } else {
+                    ramp
+                };
+                create_glyph_image(&grey, font, &chars, self.glyph_match, self.cell_fit)
+            }
+            _ if self.ascii_type == AsciiType::HalfBlock => ascii_type_half_block(&grid),
+            _ if self.ascii_type == AsciiType::Edge => {
+                let edge_options = EdgeOptions {
+                    detection: self.edge_detection,
+                    threshold: self.edge_threshold,
+                    chars: self.edge_chars,
+                    blend: self.edge_blend,
+                };
+                create_edge_image(
+                    &grey,
+                    dither.as_ref().unwrap_or(&grey),
+                    &ramp,
+                    &edge_options,
+                )
+            }
+            _ => create_ascii_image(dither.as_ref().unwrap_or(&grey), &ramp),
+        };
+
+        let ansi = match &colors {
+            Some(colors) => create_ansi_text(&text, colors),
+            None => text.clone(),
+        };
+
+        Ok(Conversion {
+            cols: grid.cols,
+            rows: grid.rows,
+            width,
+            height,
+            scaled,
+            grey,
+            dither,
+            text,
+            colors,
+            ansi,
+        })
+    }
+}
+
+pub fn load_image(filename: &str) -> Result<DynamicImage, Img2AscError> {
+    let reader = ImageReader::open(filename)?.with_guessed_format()?;
+    if reader.format().is_none() {
+        return Err(Img2AscError::UnsupportedFormat(filename.to_string()));
+    }
+    Ok(reader.decode()?)
+}
+
+/// The cols and rows for an image of the given size, a free width or height follows
+/// the aspect ratio of the image with chars half as wide as high.
+pub fn calc_image_size(
+    img_width: u32,
+    img_height: u32,
+    ascii_width: u16,
+    ascii_height: u16,
+) -> (u32, u32) {
+    let ar = img_height as f32 / img_width as f32;
+
+    let cols: u32;
+    let rows: u32;
+
+    if ascii_width == 0 && ascii_height == 0 {
+        cols = 80;
+        rows = (cols as f32 * ASCII_X_DOTS as f32 * ar / ASCII_Y_DOTS as f32).ceil() as u32;
+    } else if ascii_width == 0 && ascii_height > 0 {
+        rows = ascii_height as u32;
+        cols = (rows as f32 * ASCII_Y_DOTS as f32 / (ar * ASCII_X_DOTS as f32)).ceil() as u32;
+    } else if ascii_width > 0 && ascii_height == 0 {
+        cols = ascii_width as u32;
+        rows = (cols as f32 * ASCII_X_DOTS as f32 * ar / ASCII_Y_DOTS as f32).ceil() as u32;
+    } else {
+        cols = ascii_width as u32;
+        rows = ascii_height as u32;
+    }
+
+    (cols, rows)
+}
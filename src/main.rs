@@ -20,38 +20,102 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-mod ascii_image;
 mod config;
-mod dithering;
-mod greyscaling;
-mod resizing;
 
-use crate::ascii_image::*;
 use crate::config::*;
-use crate::dithering::*;
-use crate::greyscaling::*;
-use crate::resizing::*;
-use image::{DynamicImage, ExtendedColorType, ImageFormat, ImageReader};
+use image::ImageFormat;
+use img2asc::ansi_art::{create_ans, load_ansi_art};
+use img2asc::bitmap_font::BitmapFont;
+use img2asc::coloring::{AnsiColor, CellColor, ColorMode, create_ansi_text, create_palette_colors};
+use img2asc::html_export::create_html;
+use img2asc::palette::Palette;
+use img2asc::pixel_buffer::PixelBuffer;
+use img2asc::png_export::{create_text_image, encode_png};
+use img2asc::svg_export::create_svg;
+use img2asc::{Img2AscError, load_image};
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::process::ExitCode;
 
-fn main() -> Result<(), i32> {
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+// the chars of the ascii image with their colors
+struct AsciiArt {
+    text: String,
+    colors: Option<Vec<CellColor>>,
+    ansi: String,
+    cols: u32,
+}
+
+fn run() -> Result<(), Img2AscError> {
     // get arguments
-    let cfg = Configuration::parse();
+    let cfg = Configuration::parse()?;
+
+    let art = if cfg.ansi_input {
+        import_ansi_art(&cfg)?
+    } else {
+        convert_image(&cfg)?
+    };
+
+    if cfg.show_ascii {
+        println!("\n{}", art.ansi);
+    }
+
+    let asc_image = match cfg.format {
+        OutputFormat::Text => art.ansi.into_bytes(),
+        OutputFormat::Html => {
+            create_html(&art.text, art.colors.as_deref(), &cfg.style, &cfg.html).into_bytes()
+        }
+        OutputFormat::Svg => {
+            create_svg(&art.text, art.colors.as_deref(), &cfg.style, &cfg.svg).into_bytes()
+        }
+        // the glyphs of the font chosen for the ascii image, or the built-in ones
+        OutputFormat::Png => {
+            let font = cfg
+                .converter
+                .font
+                .clone()
+                .unwrap_or_else(BitmapFont::embedded);
+            let image = create_text_image(
+                &art.text,
+                art.colors.as_deref(),
+                &font,
+                cfg.style.background,
+            );
+            encode_png(&image)?
+        }
+        OutputFormat::Ans => create_ans(&art.text, art.colors.as_deref(), art.cols, &cfg.ans),
+    };
+
+    let file = File::create(&cfg.output)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(&asc_image)?;
+    writer.flush()?;
+
+    println!("Ascii image {} successfully written.", cfg.output);
+
+    Ok(())
+}
 
+fn convert_image(cfg: &Configuration) -> Result<AsciiArt, Img2AscError> {
     // load original image
-    let original_image = load_image(&cfg.filename).unwrap();
+    let original_image = load_image(&cfg.filename)?;
 
     // calc the sizes for ascii and braille versions
-    let (mut width, mut height) = calc_image_size(
-        original_image.width(),
-        original_image.height(),
-        cfg.ascii_width,
-        cfg.ascii_height,
-    );
+    let (cols, rows) = cfg
+        .converter
+        .ascii_size(original_image.width(), original_image.height())?;
 
     // print conversion settings to console
-    cfg.print(width, height);
+    cfg.print(cols, rows);
     println!(
         "Image {} loaded successfully (size: {}x{}).",
         cfg.filename,
@@ -59,128 +123,81 @@ fn main() -> Result<(), i32> {
         original_image.height()
     );
 
-    // use different size for braille image
-    if cfg.ascii_type == AsciiType::Braille {
-        width *= ASCII_X_DOTS as u32;
-        height *= ASCII_Y_DOTS as u32;
-    }
-
-    let scaled_vec = create_resized_image(&original_image, width, height, cfg.resize_opt);
-    save_image("scaled.png", &scaled_vec, width, height);
+    let conversion = cfg.converter.convert(&original_image)?;
 
-    let grey_vec = create_greyscale_image(
-        &scaled_vec,
-        width,
-        height,
-        cfg.grey_scale,
-        cfg.invert,
-        cfg.alpha_threshold,
-    );
-    save_image("grey.png", &grey_vec, width, height);
+    save_image("scaled.png", &conversion.scaled);
+    save_image("grey.png", &conversion.grey);
+    if let Some(bitmap) = &conversion.dither {
+        save_image("dither.png", bitmap);
+    }
 
-    // the vector has more than 1 channel, we convert it to only 1 channel
-    let asc_vec = if cfg.ascii_type == AsciiType::Dot || cfg.ascii_type == AsciiType::Braille {
-        let dither_vec = create_dither_image(&grey_vec, width, height, cfg.threshold, cfg.dither);
-        save_image("dither.png", &dither_vec, width, height);
-        create_single_channel_vec(&dither_vec, width, height)
-    } else {
-        create_single_channel_vec(&grey_vec, width, height)
-    };
+    Ok(AsciiArt {
+        text: conversion.text,
+        colors: conversion.colors,
+        ansi: conversion.ansi,
+        cols: conversion.cols,
+    })
+}
 
-    let asc_image: String = if cfg.ascii_type == AsciiType::Braille {
-        ascii_type_braille(&asc_vec, width, height)
-    } else {
-        create_ascii_image(&asc_vec, cfg.ascii_type, width)
-    };
+// the colors stay 24 bit, unless 256 or 16 colors are chosen
+fn import_ansi_art(cfg: &Configuration) -> Result<AsciiArt, Img2AscError> {
+    let palette = cfg.custom_palette.then_some(&cfg.converter.palette);
+    let art = load_ansi_art(&cfg.filename, palette)?;
 
-    if cfg.show_ascii {
-        println!("\n{asc_image}");
+    println!(
+        "ANSI art {} loaded successfully (size: {} cols x {} rows).",
+        cfg.filename, art.cols, art.rows
+    );
+    if let Some(sauce) = &art.sauce {
+        println!(
+            "SAUCE: {} by {} {} ({})",
+            sauce.options.title, sauce.options.author, sauce.options.group, sauce.options.font
+        );
     }
 
-    let file = match File::create(&cfg.output) {
-        Ok(file) => file,
-        Err(err) => {
-            eprintln!("Error: {}", err);
-            return Err(5);
+    let space = cfg.converter.color_space;
+    let colors = match cfg.converter.color {
+        ColorMode::Ansi256 => create_palette_colors(&art.colors, &Palette::xterm256(space)),
+        ColorMode::Ansi16 => {
+            create_palette_colors(&art.colors, &Palette::ansi16(&cfg.converter.palette, space))
+        }
+        _ => art.colors,
+    };
+    // the .ans output keeps the indexes of the file, the text 24 bit colors
+    let ansi = match cfg.converter.color {
+        ColorMode::Ansi256 | ColorMode::Ansi16 => create_ansi_text(&art.text, &colors),
+        _ => {
+            let truecolor: Vec<CellColor> = colors
+                .iter()
+                .map(|color| CellColor {
+                    fg: AnsiColor::rgb(color.fg.rgb),
+                    bg: color.bg.map(|bg| AnsiColor::rgb(bg.rgb)),
+                })
+                .collect();
+            create_ansi_text(&art.text, &truecolor)
         }
     };
 
-    let mut writer = BufWriter::new(file);
-    writer
-        .write_all(&asc_image.into_bytes())
-        .unwrap_or_else(|err| {
-            println!("Error: {}", err);
-        });
-    writer.flush().unwrap_or_else(|err| {
-        println!("Error: {}", err);
-    });
-
-    println!("Ascii image {} successfully written.", cfg.output);
-
-    Ok(())
-}
-
-fn load_image(filename: &str) -> std::io::Result<DynamicImage> {
-    let new_image = ImageReader::open(filename)
-        .unwrap_or_else(|err| {
-            eprintln!("Error: {}", err);
-            std::process::exit(1);
-        })
-        .with_guessed_format()
-        .unwrap_or_else(|err| {
-            eprintln!("Error: {}", err);
-            std::process::exit(2);
-        })
-        .decode()
-        .unwrap_or_else(|err| {
-            eprintln!("Error: {}", err);
-            std::process::exit(3);
-        });
-    Ok(new_image)
+    Ok(AsciiArt {
+        ansi,
+        text: art.text,
+        colors: Some(colors),
+        cols: art.cols,
+    })
 }
 
 /// ugly coded, only for debug purposes
-fn save_image(filename: &str, buffer: &[u8], width: u32, height: u32) {
+fn save_image(filename: &str, buffer: &impl PixelBuffer) {
     #[cfg(debug_assertions)]
     image::save_buffer_with_format(
         filename,
-        buffer,
-        width,
-        height,
-        ExtendedColorType::Rgba8,
+        &buffer.to_bytes(),
+        buffer.width(),
+        buffer.height(),
+        buffer.color_type(),
         ImageFormat::Png,
     )
-    .unwrap();
-}
-
-fn calc_image_size(
-    img_width: u32,
-    img_height: u32,
-    ascii_width: u16,
-    ascii_height: u16,
-) -> (u32, u32) {
-    let ar: f32 = if img_width > img_height {
-        img_height as f32 / img_width as f32
-    } else {
-        img_width as f32 / img_height as f32
-    };
-
-    let cols: u32;
-    let rows: u32;
-
-    if ascii_width == 0 && ascii_height == 0 {
-        cols = 80;
-        rows = (cols as f32 * ASCII_X_DOTS as f32 * ar / ASCII_Y_DOTS as f32).ceil() as u32;
-    } else if ascii_width == 0 && ascii_height > 0 {
-        rows = ascii_height as u32;
-        cols = (rows as f32 * ASCII_Y_DOTS as f32 * ar / ASCII_X_DOTS as f32).ceil() as u32;
-    } else if ascii_width > 0 && ascii_height == 0 {
-        cols = ascii_width as u32;
-        rows = (cols as f32 * ASCII_X_DOTS as f32 * ar / ASCII_Y_DOTS as f32).ceil() as u32;
-    } else {
-        cols = ascii_width as u32;
-        rows = ascii_height as u32;
-    }
-
-    (cols, rows)
+    .unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+    });
 }
@@ -0,0 +1,69 @@
+use crate::pixel_buffer::RgbaFloatBuffer;
+use image::{DynamicImage, GenericImageView};
+
+const U8_MAX: f32 = u8::MAX as f32;
+const U16_MAX: f32 = u16::MAX as f32;
+
+fn luma_to_rgba(luma: f32, alpha: f32) -> [f32; 4] {
+    [luma, luma, luma, alpha]
+}
+
+/// Converts every pixel layout of the image crate into RGBA floats from 0.0 to 1.0.
+/// Grey images are expanded to RGB, missing alpha channels are set to opaque.
+pub fn create_normalized_image(image: &DynamicImage) -> RgbaFloatBuffer {
+    let (width, height) = image.dimensions();
+
+    let data: Vec<f32> = match image {
+        DynamicImage::ImageLuma8(img) => img
+            .pixels()
+            .flat_map(|p| luma_to_rgba(p[0] as f32 / U8_MAX, 1.))
+            .collect(),
+        DynamicImage::ImageLumaA8(img) => img
+            .pixels()
+            .flat_map(|p| luma_to_rgba(p[0] as f32 / U8_MAX, p[1] as f32 / U8_MAX))
+            .collect(),
+        DynamicImage::ImageRgb8(img) => img
+            .pixels()
+            .flat_map(|p| {
+                [
+                    p[0] as f32 / U8_MAX,
+                    p[1] as f32 / U8_MAX,
+                    p[2] as f32 / U8_MAX,
+                    1.,
+                ]
+            })
+            .collect(),
+        DynamicImage::ImageRgba8(img) => img.as_raw().iter().map(|v| *v as f32 / U8_MAX).collect(),
+        DynamicImage::ImageLuma16(img) => img
+            .pixels()
+            .flat_map(|p| luma_to_rgba(p[0] as f32 / U16_MAX, 1.))
+            .collect(),
+        DynamicImage::ImageLumaA16(img) => img
+            .pixels()
+            .flat_map(|p| luma_to_rgba(p[0] as f32 / U16_MAX, p[1] as f32 / U16_MAX))
+            .collect(),
+        DynamicImage::ImageRgb16(img) => img
+            .pixels()
+            .flat_map(|p| {
+                [
+                    p[0] as f32 / U16_MAX,
+                    p[1] as f32 / U16_MAX,
+                    p[2] as f32 / U16_MAX,
+                    1.,
+                ]
+            })
+            .collect(),
+        DynamicImage::ImageRgba16(img) => {
+            img.as_raw().iter().map(|v| *v as f32 / U16_MAX).collect()
+        }
+        DynamicImage::ImageRgb32F(img) => {
+            img.pixels().flat_map(|p| [p[0], p[1], p[2], 1.]).collect()
+        }
+        DynamicImage::ImageRgba32F(img) => img.as_raw().to_vec(),
+        // new layouts of the image crate, let it do the conversion
+        _ => image.to_rgba32f().into_raw(),
+    };
+
+    RgbaFloatBuffer::from_raw(width, height, data)
+        .expect("every layout is converted to 4 channels per pixel")
+}
@@ -0,0 +1,232 @@
+use crate::Img2AscError;
+use crate::gamma::srgb_to_linear;
+use std::fs;
+
+pub const PALETTE_XTERM: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0xcd, 0x00, 0x00],
+    [0x00, 0xcd, 0x00],
+    [0xcd, 0xcd, 0x00],
+    [0x00, 0x00, 0xee],
+    [0xcd, 0x00, 0xcd],
+    [0x00, 0xcd, 0xcd],
+    [0xe5, 0xe5, 0xe5],
+    [0x7f, 0x7f, 0x7f],
+    [0xff, 0x00, 0x00],
+    [0x00, 0xff, 0x00],
+    [0xff, 0xff, 0x00],
+    [0x5c, 0x5c, 0xff],
+    [0xff, 0x00, 0xff],
+    [0x00, 0xff, 0xff],
+    [0xff, 0xff, 0xff],
+];
+
+pub const PALETTE_VGA: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0xaa, 0x00, 0x00],
+    [0x00, 0xaa, 0x00],
+    [0xaa, 0x55, 0x00],
+    [0x00, 0x00, 0xaa],
+    [0xaa, 0x00, 0xaa],
+    [0x00, 0xaa, 0xaa],
+    [0xaa, 0xaa, 0xaa],
+    [0x55, 0x55, 0x55],
+    [0xff, 0x55, 0x55],
+    [0x55, 0xff, 0x55],
+    [0xff, 0xff, 0x55],
+    [0x55, 0x55, 0xff],
+    [0xff, 0x55, 0xff],
+    [0x55, 0xff, 0xff],
+    [0xff, 0xff, 0xff],
+];
+
+pub const PALETTE_SOLARIZED: [[u8; 3]; 16] = [
+    [0x07, 0x36, 0x42],
+    [0xdc, 0x32, 0x2f],
+    [0x85, 0x99, 0x00],
+    [0xb5, 0x89, 0x00],
+    [0x26, 0x8b, 0xd2],
+    [0xd3, 0x36, 0x82],
+    [0x2a, 0xa1, 0x98],
+    [0xee, 0xe8, 0xd5],
+    [0x00, 0x2b, 0x36],
+    [0xcb, 0x4b, 0x16],
+    [0x58, 0x6e, 0x75],
+    [0x65, 0x7b, 0x83],
+    [0x83, 0x94, 0x96],
+    [0x6c, 0x71, 0xc4],
+    [0x93, 0xa1, 0xa1],
+    [0xfd, 0xf6, 0xe3],
+];
+
+/// Names of the built-in 16 color palettes for `get_named_palette`.
+pub const PALETTE_NAMES: [&str; 3] = ["solarized", "vga", "xterm"];
+
+// the levels of the 6x6x6 color cube of xterm
+const XTERM_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The color space for the nearest color search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    Cielab,
+    Oklab,
+}
+
+/// A list of ANSI colors with their index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    entries: Vec<(u8, [u8; 3])>,
+    coords: Vec<[f32; 3]>,
+    space: ColorSpace,
+}
+
+// the white point D65 for CIELAB
+const XYZ_WHITE: [f32; 3] = [0.95047, 1., 1.08883];
+
+fn lab_f(t: f32) -> f32 {
+    if t > 216. / 24389. {
+        t.cbrt()
+    } else {
+        (24389. / 27. * t + 16.) / 116.
+    }
+}
+
+fn linear_to_cielab(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / XYZ_WHITE[0];
+    let y = (0.2126 * r + 0.7152 * g + 0.0722 * b) / XYZ_WHITE[1];
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / XYZ_WHITE[2];
+    let (fx, fy, fz) = (lab_f(x), lab_f(y), lab_f(z));
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+}
+
+fn linear_to_oklab(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let l = (0.4122215 * r + 0.5363325 * g + 0.051446 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.107397 * b).cbrt();
+    let s = (0.0883025 * r + 0.2817188 * g + 0.6299787 * b).cbrt();
+    [
+        0.2104543 * l + 0.7936178 * m - 0.004072 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904 * l + 0.7827718 * m - 0.8086758 * s,
+    ]
+}
+
+impl ColorSpace {
+    /// Converts a sRGB color from 0.0 to 1.0 into the color space.
+    pub fn from_srgb(&self, srgb: [f32; 3]) -> [f32; 3] {
+        let linear = srgb.map(|v| srgb_to_linear(v.clamp(0., 1.)));
+        match self {
+            ColorSpace::Cielab => linear_to_cielab(linear),
+            ColorSpace::Oklab => linear_to_oklab(linear),
+        }
+    }
+}
+
+impl Palette {
+    fn new(entries: Vec<(u8, [u8; 3])>, space: ColorSpace) -> Palette {
+        let coords = entries
+            .iter()
+            .map(|(_, rgb)| space.from_srgb(rgb.map(|v| v as f32 / 255.)))
+            .collect();
+        Palette {
+            entries,
+            coords,
+            space,
+        }
+    }
+
+    /// The 16 colors of the terminal with the indexes 0 to 15.
+    pub fn ansi16(colors: &[[u8; 3]; 16], space: ColorSpace) -> Palette {
+        Palette::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(n, rgb)| (n as u8, *rgb))
+                .collect(),
+            space,
+        )
+    }
+
+    /// The color cube and grey ramp of xterm with the indexes 16 to 255. The first 16
+    /// colors are left out, because they depend on the terminal.
+    pub fn xterm256(space: ColorSpace) -> Palette {
+        let mut entries = Vec::with_capacity(240);
+        for r in XTERM_CUBE_LEVELS {
+            for g in XTERM_CUBE_LEVELS {
+                for b in XTERM_CUBE_LEVELS {
+                    entries.push((16 + entries.len() as u8, [r, g, b]));
+                }
+            }
+        }
+        for n in 0..24 {
+            let grey = 8 + n * 10;
+            entries.push((232 + n, [grey, grey, grey]));
+        }
+        Palette::new(entries, space)
+    }
+
+    /// The ANSI index and color of every entry.
+    pub fn entries(&self) -> &[(u8, [u8; 3])] {
+        &self.entries
+    }
+
+    /// The index and color nearest to a sRGB color from 0.0 to 1.0.
+    pub fn find_nearest(&self, srgb: [f32; 3]) -> (u8, [u8; 3]) {
+        self.entries[self.find_nearest_entry(srgb).0]
+    }
+
+    /// The position in `entries` of the nearest color and the distance to it.
+    pub fn find_nearest_entry(&self, srgb: [f32; 3]) -> (usize, f32) {
+        let target = self.space.from_srgb(srgb);
+        let distance = |c: &[f32; 3]| {
+            (c[0] - target[0]).powi(2) + (c[1] - target[1]).powi(2) + (c[2] - target[2]).powi(2)
+        };
+        self.coords
+            .iter()
+            .map(distance)
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((0, 0.))
+    }
+}
+
+pub fn get_named_palette(name: &str) -> Option<[[u8; 3]; 16]> {
+    match name.to_lowercase().as_str() {
+        "solarized" => Some(PALETTE_SOLARIZED),
+        "vga" => Some(PALETTE_VGA),
+        "xterm" => Some(PALETTE_XTERM),
+        _ => None,
+    }
+}
+
+pub fn load_palette(filename: &str) -> Result<[[u8; 3]; 16], Img2AscError> {
+    parse_palette(&fs::read_to_string(filename)?)
+}
+
+/// Reads a color as hex value like `#cd0000`, the `#` is optional.
+pub fn parse_hex_color(value: &str) -> Option<[u8; 3]> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    // from_str_radix would take a sign too
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+/// Reads 16 colors as hex values like `#cd0000`, separated by line breaks, spaces or commas.
+pub fn parse_palette(text: &str) -> Result<[[u8; 3]; 16], Img2AscError> {
+    let colors = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            parse_hex_color(v)
+                .ok_or_else(|| Img2AscError::InvalidPalette(format!("{} is no hex color", v)))
+        })
+        .collect::<Result<Vec<[u8; 3]>, _>>()?;
+
+    colors.try_into().map_err(|colors: Vec<[u8; 3]>| {
+        Img2AscError::InvalidPalette(format!("expected 16 colors, found {}", colors.len()))
+    })
+}
@@ -0,0 +1,278 @@
+use image::ExtendedColorType;
+
+/// Common interface of the image buffers passed between the pipeline stages.
+pub trait PixelBuffer {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+    fn color_type(&self) -> ExtendedColorType;
+    /// Raw bytes in the layout of `color_type`, e.g. for saving with the image crate.
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+fn get_offset(x: u32, y: u32, width: u32) -> usize {
+    (width * y + x) as usize
+}
+
+/// Converts a float from 0.0 to 1.0 into a value from 0 to 255.
+pub fn quantize_u8(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
+
+/// 8 bit RGBA pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaBuffer {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl RgbaBuffer {
+    pub fn new(width: u32, height: u32) -> RgbaBuffer {
+        RgbaBuffer {
+            width,
+            height,
+            data: vec![0; (width * height) as usize * 4],
+        }
+    }
+
+    /// Returns `None` if the vector size doesn't match `width * height * 4`.
+    pub fn from_raw(width: u32, height: u32, data: Vec<u8>) -> Option<RgbaBuffer> {
+        if data.len() != (width * height) as usize * 4 {
+            return None;
+        }
+        Some(RgbaBuffer {
+            width,
+            height,
+            data,
+        })
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = get_offset(x, y, self.width) * 4;
+        [
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ]
+    }
+
+    pub fn put_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        let offset = get_offset(x, y, self.width) * 4;
+        self.data[offset..offset + 4].copy_from_slice(&pixel);
+    }
+
+    pub fn as_raw(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl PixelBuffer for RgbaBuffer {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn color_type(&self) -> ExtendedColorType {
+        ExtendedColorType::Rgba8
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// RGBA pixels as floats from 0.0 to 1.0, used for all calculations before quantization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaFloatBuffer {
+    width: u32,
+    height: u32,
+    data: Vec<f32>,
+}
+
+impl RgbaFloatBuffer {
+    pub fn new(width: u32, height: u32) -> RgbaFloatBuffer {
+        RgbaFloatBuffer {
+            width,
+            height,
+            data: vec![0.; (width * height) as usize * 4],
+        }
+    }
+
+    /// Returns `None` if the vector size doesn't match `width * height * 4`.
+    pub fn from_raw(width: u32, height: u32, data: Vec<f32>) -> Option<RgbaFloatBuffer> {
+        if data.len() != (width * height) as usize * 4 {
+            return None;
+        }
+        Some(RgbaFloatBuffer {
+            width,
+            height,
+            data,
+        })
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        let offset = get_offset(x, y, self.width) * 4;
+        [
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ]
+    }
+
+    pub fn put_pixel(&mut self, x: u32, y: u32, pixel: [f32; 4]) {
+        let offset = get_offset(x, y, self.width) * 4;
+        self.data[offset..offset + 4].copy_from_slice(&pixel);
+    }
+
+    pub fn as_raw(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Quantizes every channel to 8 bit, values outside 0.0 to 1.0 are clamped.
+    pub fn to_rgba8(&self) -> RgbaBuffer {
+        RgbaBuffer {
+            width: self.width,
+            height: self.height,
+            data: self.data.iter().map(|v| quantize_u8(*v)).collect(),
+        }
+    }
+}
+
+impl PixelBuffer for RgbaFloatBuffer {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn color_type(&self) -> ExtendedColorType {
+        ExtendedColorType::Rgba8
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_rgba8().data
+    }
+}
+
+/// 8 bit grey values, one per pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreyBuffer {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl GreyBuffer {
+    pub fn new(width: u32, height: u32) -> GreyBuffer {
+        GreyBuffer {
+            width,
+            height,
+            data: vec![0; (width * height) as usize],
+        }
+    }
+
+    /// Returns `None` if the vector size doesn't match `width * height`.
+    pub fn from_raw(width: u32, height: u32, data: Vec<u8>) -> Option<GreyBuffer> {
+        if data.len() != (width * height) as usize {
+            return None;
+        }
+        Some(GreyBuffer {
+            width,
+            height,
+            data,
+        })
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        self.data[get_offset(x, y, self.width)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, value: u8) {
+        self.data[get_offset(x, y, self.width)] = value;
+    }
+
+    pub fn as_raw(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl PixelBuffer for GreyBuffer {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn color_type(&self) -> ExtendedColorType {
+        ExtendedColorType::L8
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// Black and white pixels, `true` is white.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitmapBuffer {
+    width: u32,
+    height: u32,
+    data: Vec<bool>,
+}
+
+impl BitmapBuffer {
+    pub fn new(width: u32, height: u32) -> BitmapBuffer {
+        BitmapBuffer {
+            width,
+            height,
+            data: vec![false; (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.data[get_offset(x, y, self.width)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        self.data[get_offset(x, y, self.width)] = value;
+    }
+
+    /// Converts to grey values, black is 0 and white is 255.
+    pub fn to_grey(&self) -> GreyBuffer {
+        GreyBuffer {
+            width: self.width,
+            height: self.height,
+            data: self.to_bytes(),
+        }
+    }
+}
+
+impl PixelBuffer for BitmapBuffer {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn color_type(&self) -> ExtendedColorType {
+        ExtendedColorType::L8
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.data
+            .iter()
+            .map(|white| if *white { 255 } else { 0 })
+            .collect()
+    }
+}
@@ -0,0 +1,64 @@
+use crate::Img2AscError;
+use crate::bitmap_font::BitmapFont;
+use crate::coloring::CellColor;
+use crate::html_export::contrast_color;
+use crate::pixel_buffer::{PixelBuffer, RgbaBuffer};
+use image::ImageFormat;
+use std::io::Cursor;
+
+/// Draws every char of the ascii image with the glyph of the font into a cell of the
+/// font size. Without colors the text is black on light and white on dark backgrounds,
+/// chars without glyph stay empty.
+pub fn create_text_image(
+    text: &str,
+    colors: Option<&[CellColor]>,
+    font: &BitmapFont,
+    background: [u8; 3],
+) -> RgbaBuffer {
+    let (cell_w, cell_h) = (font.width(), font.height());
+    let cols = text.lines().map(|l| l.chars().count()).max().unwrap_or(0) as u32;
+    let rows = text.lines().count() as u32;
+    let mut image = RgbaBuffer::new(cols * cell_w, rows * cell_h);
+    let mut colors = colors.unwrap_or_default().iter();
+    let ink = contrast_color(background);
+
+    for (row, line) in text.lines().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            let (fg, bg) = match colors.next() {
+                Some(color) => (color.fg.rgb, color.bg.map_or(background, |bg| bg.rgb)),
+                None => (ink, background),
+            };
+            for y in 0..cell_h {
+                for x in 0..cell_w {
+                    let [r, g, b] = if font.get(c, x, y) { fg } else { bg };
+                    let px = col as u32 * cell_w + x;
+                    let py = row as u32 * cell_h + y;
+                    image.put_pixel(px, py, [r, g, b, 255]);
+                }
+            }
+        }
+        // the cells after short lines have the background
+        for px in line.chars().count() as u32 * cell_w..image.width() {
+            for y in 0..cell_h {
+                let [r, g, b] = background;
+                image.put_pixel(px, row as u32 * cell_h + y, [r, g, b, 255]);
+            }
+        }
+    }
+
+    image
+}
+
+/// Encodes an image as PNG file.
+pub fn encode_png(image: &impl PixelBuffer) -> Result<Vec<u8>, Img2AscError> {
+    let mut png = Cursor::new(Vec::new());
+    image::write_buffer_with_format(
+        &mut png,
+        &image.to_bytes(),
+        image.width(),
+        image.height(),
+        image.color_type(),
+        ImageFormat::Png,
+    )?;
+    Ok(png.into_inner())
+}
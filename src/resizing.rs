@@ -1,41 +1,61 @@
-use image::{DynamicImage, GenericImageView, Pixel};
+use crate::pixel_buffer::{PixelBuffer, RgbaFloatBuffer};
 use std::cmp::min;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ResizeType {
+    Area,
     Bicubic,
     Bilinear,
+    CatmullRom,
+    Lanczos3,
+    Mitchell,
     NearestNeighbour,
 }
 
-pub fn scaling_nearest_neighbour(image: &DynamicImage, new_width: u32, new_height: u32) -> Vec<u8> {
-    let (img_width, img_height) = image.dimensions();
-    let channels = image.get_pixel(0, 0).channels().len();
+const LANCZOS_SUPPORT: f32 = 3.;
+const CUBIC_SUPPORT: f32 = 2.;
+const MITCHELL_B: f32 = 1. / 3.;
+const MITCHELL_C: f32 = 1. / 3.;
+const CATMULL_ROM_B: f32 = 0.;
+const CATMULL_ROM_C: f32 = 0.5;
+
+// first source pixel and the weights of the following source pixels for one target pixel
+struct Contribution {
+    start: u32,
+    weights: Vec<f32>,
+}
+
+pub fn scaling_nearest_neighbour(
+    image: &RgbaFloatBuffer,
+    new_width: u32,
+    new_height: u32,
+) -> RgbaFloatBuffer {
+    let (img_width, img_height) = (image.width(), image.height());
 
-    let mut img_vec: Vec<u8> = Vec::with_capacity((new_width * new_height) as usize * channels);
+    let mut buffer = RgbaFloatBuffer::new(new_width, new_height);
 
     for y in 0..new_height {
         for x in 0..new_width {
             let mut org_x = (x as f32 / new_width as f32 * img_width as f32).round() as u32;
             let mut org_y = (y as f32 / new_height as f32 * img_height as f32).round() as u32;
 
-            org_x = min(org_x, img_width);
-            org_y = min(org_y, img_height);
+            org_x = min(org_x, img_width - 1);
+            org_y = min(org_y, img_height - 1);
 
-            let color = image.get_pixel(org_x, org_y).channels().to_owned();
-            for n in color {
-                img_vec.push(n);
-            }
+            buffer.put_pixel(x, y, image.get_pixel(org_x, org_y));
         }
     }
-    img_vec
+    buffer
 }
 
-pub fn scaling_bilinear(image: &DynamicImage, new_width: u32, new_height: u32) -> Vec<u8> {
-    let (img_width, img_height) = image.dimensions();
-    let channels = image.get_pixel(0, 0).channels().len();
+pub fn scaling_bilinear(
+    image: &RgbaFloatBuffer,
+    new_width: u32,
+    new_height: u32,
+) -> RgbaFloatBuffer {
+    let (img_width, img_height) = (image.width(), image.height());
 
-    let mut img_vec: Vec<u8> = Vec::with_capacity((new_width * new_height) as usize * channels);
+    let mut buffer = RgbaFloatBuffer::new(new_width, new_height);
 
     let scale_x: f32 = img_width as f32 / new_width as f32;
     let scale_y: f32 = img_height as f32 / new_height as f32;
@@ -44,29 +64,29 @@ pub fn scaling_bilinear(image: &DynamicImage, new_width: u32, new_height: u32) -
         for x in 0..new_width {
             let x0 = (scale_x * x as f32).floor() as u32;
             let y0 = (scale_y * y as f32).floor() as u32;
-            let x1 = (scale_x * x as f32).ceil() as u32;
-            let y1 = (scale_y * y as f32).ceil() as u32;
+            let x1 = min((scale_x * x as f32).ceil() as u32, img_width - 1);
+            let y1 = min((scale_y * y as f32).ceil() as u32, img_height - 1);
 
             let x_weight = (scale_x * x as f32) - x0 as f32;
             let y_weight = (scale_y * y as f32) - y0 as f32;
 
-            let color_x0y0 = image.get_pixel(x0, y0).channels().to_owned();
-            let color_x1y0 = image.get_pixel(x1, y0).channels().to_owned();
-            let color_x0y1 = image.get_pixel(x0, y1).channels().to_owned();
-            let color_x1y1 = image.get_pixel(x1, y1).channels().to_owned();
-
-            for c in 0..channels {
-                let interpolated_color = color_x0y0[c] as f32 * (1. - x_weight) * (1. - y_weight)
-                    + color_x1y0[c] as f32 * x_weight * (1. - y_weight)
-                    + color_x0y1[c] as f32 * (1. - x_weight) * y_weight
-                    + color_x1y1[c] as f32 * x_weight * y_weight;
-
-                img_vec.push(interpolated_color.round() as u8);
+            let color_x0y0 = image.get_pixel(x0, y0);
+            let color_x1y0 = image.get_pixel(x1, y0);
+            let color_x0y1 = image.get_pixel(x0, y1);
+            let color_x1y1 = image.get_pixel(x1, y1);
+
+            let mut pixel = [0.; 4];
+            for c in 0..4 {
+                pixel[c] = color_x0y0[c] * (1. - x_weight) * (1. - y_weight)
+                    + color_x1y0[c] * x_weight * (1. - y_weight)
+                    + color_x0y1[c] * (1. - x_weight) * y_weight
+                    + color_x1y1[c] * x_weight * y_weight;
             }
+            buffer.put_pixel(x, y, pixel);
         }
     }
 
-    img_vec
+    buffer
 }
 
 fn clamp_f32(value: f32, min_val: f32, max_val: f32) -> f32 {
@@ -79,7 +99,7 @@ fn clamp_f32(value: f32, min_val: f32, max_val: f32) -> f32 {
     }
 }
 
-fn get_pixel_clamped(image: &DynamicImage, x: i32, y: i32) -> Vec<u8> {
+fn get_pixel_clamped(image: &RgbaFloatBuffer, x: i32, y: i32) -> [f32; 4] {
     let px: u32;
     if x < 0 {
         px = 0;
@@ -96,12 +116,7 @@ fn get_pixel_clamped(image: &DynamicImage, x: i32, y: i32) -> Vec<u8> {
     } else {
         py = y as u32;
     }
-    let channels = image.get_pixel(px, py).channels().len();
-    let mut pixel: Vec<u8> = Vec::with_capacity(channels);
-    for n in 0..channels {
-        pixel.push(image.get_pixel(px, py).channels()[n]);
-    }
-    pixel
+    image.get_pixel(px, py)
 }
 
 fn cubic_hermite(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
@@ -112,12 +127,15 @@ fn cubic_hermite(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
     a * t * t * t + b * t * t + c * t + d
 }
 
-pub fn scaling_bicubic(image: &DynamicImage, new_width: u32, new_height: u32) -> Vec<u8> {
+pub fn scaling_bicubic(
+    image: &RgbaFloatBuffer,
+    new_width: u32,
+    new_height: u32,
+) -> RgbaFloatBuffer {
     let img_width = image.width();
     let img_height = image.height();
-    let channels = image.get_pixel(0, 0).channels().len();
 
-    let mut img_vec: Vec<u8> = Vec::with_capacity((new_width * new_height) as usize * channels);
+    let mut buffer = RgbaFloatBuffer::new(new_width, new_height);
 
     for y in 0..new_height {
         let py = y as f32 / new_height as f32;
@@ -157,55 +175,222 @@ pub fn scaling_bicubic(image: &DynamicImage, new_width: u32, new_height: u32) ->
             let p23 = get_pixel_clamped(image, x_pos + 1, y_pos + 2);
             let p33 = get_pixel_clamped(image, x_pos + 2, y_pos + 2);
 
-            for c in 0..channels {
-                let col0 = cubic_hermite(
-                    p00[c] as f32,
-                    p10[c] as f32,
-                    p20[c] as f32,
-                    p30[c] as f32,
-                    x_fract,
-                );
-                let col1 = cubic_hermite(
-                    p01[c] as f32,
-                    p11[c] as f32,
-                    p21[c] as f32,
-                    p31[c] as f32,
-                    x_fract,
-                );
-                let col2 = cubic_hermite(
-                    p02[c] as f32,
-                    p12[c] as f32,
-                    p22[c] as f32,
-                    p32[c] as f32,
-                    x_fract,
-                );
-                let col3 = cubic_hermite(
-                    p03[c] as f32,
-                    p13[c] as f32,
-                    p23[c] as f32,
-                    p33[c] as f32,
-                    x_fract,
-                );
+            let mut pixel = [0.; 4];
+            for c in 0..4 {
+                let col0 = cubic_hermite(p00[c], p10[c], p20[c], p30[c], x_fract);
+                let col1 = cubic_hermite(p01[c], p11[c], p21[c], p31[c], x_fract);
+                let col2 = cubic_hermite(p02[c], p12[c], p22[c], p32[c], x_fract);
+                let col3 = cubic_hermite(p03[c], p13[c], p23[c], p33[c], x_fract);
 
                 let value = cubic_hermite(col0, col1, col2, col3, y_fract);
 
-                img_vec.push(clamp_f32(value, 0., 255.) as u8);
+                pixel[c] = clamp_f32(value, 0., 1.);
             }
+            buffer.put_pixel(x, y, pixel);
         }
     }
 
-    img_vec
+    buffer
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0. {
+        1.
+    } else {
+        let px = std::f32::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+fn kernel_lanczos3(x: f32) -> f32 {
+    if x.abs() < LANCZOS_SUPPORT {
+        sinc(x) * sinc(x / LANCZOS_SUPPORT)
+    } else {
+        0.
+    }
+}
+
+// the cubic filter family of Mitchell and Netravali
+fn kernel_cubic_bc(x: f32, b: f32, c: f32) -> f32 {
+    let x = x.abs();
+    if x < 1. {
+        ((12. - 9. * b - 6. * c) * x * x * x + (-18. + 12. * b + 6. * c) * x * x + (6. - 2. * b))
+            / 6.
+    } else if x < 2. {
+        ((-b - 6. * c) * x * x * x
+            + (6. * b + 30. * c) * x * x
+            + (-12. * b - 48. * c) * x
+            + (8. * b + 24. * c))
+            / 6.
+    } else {
+        0.
+    }
+}
+
+fn kernel_mitchell(x: f32) -> f32 {
+    kernel_cubic_bc(x, MITCHELL_B, MITCHELL_C)
+}
+
+fn kernel_catmull_rom(x: f32) -> f32 {
+    kernel_cubic_bc(x, CATMULL_ROM_B, CATMULL_ROM_C)
+}
+
+// the weight of every source pixel is the length it covers of the target pixel
+fn calc_area_contributions(src_size: u32, dst_size: u32) -> Vec<Contribution> {
+    let scale = src_size as f32 / dst_size as f32;
+    let mut contributions = Vec::with_capacity(dst_size as usize);
+
+    for i in 0..dst_size {
+        let left = i as f32 * scale;
+        let right = left + scale;
+        let start = min(left.floor() as u32, src_size - 1);
+        let end = min(right.ceil() as u32, src_size).max(start + 1);
+
+        let weights = (start..end)
+            .map(|j| (right.min(j as f32 + 1.) - left.max(j as f32)).max(0.))
+            .collect();
+
+        contributions.push(normalize_contribution(Contribution { start, weights }));
+    }
+
+    contributions
+}
+
+// the filter is stretched on downscaling, so every source pixel is used
+fn calc_filter_contributions(
+    src_size: u32,
+    dst_size: u32,
+    kernel: fn(f32) -> f32,
+    support: f32,
+) -> Vec<Contribution> {
+    let scale = src_size as f32 / dst_size as f32;
+    let filter_scale = scale.max(1.);
+    let support = support * filter_scale;
+    let mut contributions = Vec::with_capacity(dst_size as usize);
+
+    for i in 0..dst_size {
+        let center = (i as f32 + 0.5) * scale;
+        let first = (center - support).floor() as i64;
+        let last = (center + support).ceil() as i64;
+
+        // pixels outside of the image are clamped to the border pixels
+        let start = first.clamp(0, src_size as i64 - 1) as u32;
+        let end = last.clamp(0, src_size as i64 - 1) as u32;
+        let mut weights = vec![0.; (end - start + 1) as usize];
+
+        for j in first..=last {
+            let w = kernel((j as f32 + 0.5 - center) / filter_scale);
+            let idx = j.clamp(start as i64, end as i64) as u32 - start;
+            weights[idx as usize] += w;
+        }
+
+        contributions.push(normalize_contribution(Contribution { start, weights }));
+    }
+
+    contributions
+}
+
+fn normalize_contribution(mut contribution: Contribution) -> Contribution {
+    let sum: f32 = contribution.weights.iter().sum();
+    if sum != 0. {
+        for w in contribution.weights.iter_mut() {
+            *w /= sum;
+        }
+    }
+    contribution
+}
+
+fn resample_separable(
+    image: &RgbaFloatBuffer,
+    x_contributions: &[Contribution],
+    y_contributions: &[Contribution],
+) -> RgbaFloatBuffer {
+    let new_width = x_contributions.len() as u32;
+    let new_height = y_contributions.len() as u32;
+
+    // 1st pass horizontal
+    let mut temp = RgbaFloatBuffer::new(new_width, image.height());
+    for y in 0..image.height() {
+        for (x, contribution) in x_contributions.iter().enumerate() {
+            let mut pixel = [0.; 4];
+            for (n, w) in contribution.weights.iter().enumerate() {
+                let color = image.get_pixel(contribution.start + n as u32, y);
+                for c in 0..4 {
+                    pixel[c] += color[c] * w;
+                }
+            }
+            temp.put_pixel(x as u32, y, pixel);
+        }
+    }
+
+    // 2nd pass vertical
+    let mut buffer = RgbaFloatBuffer::new(new_width, new_height);
+    for (y, contribution) in y_contributions.iter().enumerate() {
+        for x in 0..new_width {
+            let mut pixel = [0.; 4];
+            for (n, w) in contribution.weights.iter().enumerate() {
+                let color = temp.get_pixel(x, contribution.start + n as u32);
+                for c in 0..4 {
+                    pixel[c] += color[c] * w;
+                }
+            }
+            // the negative lobes can overshoot
+            buffer.put_pixel(x, y as u32, pixel.map(|v| clamp_f32(v, 0., 1.)));
+        }
+    }
+
+    buffer
+}
+
+pub fn scaling_area(image: &RgbaFloatBuffer, new_width: u32, new_height: u32) -> RgbaFloatBuffer {
+    resample_separable(
+        image,
+        &calc_area_contributions(image.width(), new_width),
+        &calc_area_contributions(image.height(), new_height),
+    )
+}
+
+pub fn scaling_filter(
+    image: &RgbaFloatBuffer,
+    new_width: u32,
+    new_height: u32,
+    kernel: fn(f32) -> f32,
+    support: f32,
+) -> RgbaFloatBuffer {
+    resample_separable(
+        image,
+        &calc_filter_contributions(image.width(), new_width, kernel, support),
+        &calc_filter_contributions(image.height(), new_height, kernel, support),
+    )
 }
 
 pub fn create_resized_image(
-    image: &DynamicImage,
+    image: &RgbaFloatBuffer,
     new_width: u32,
     new_height: u32,
     resize_type: ResizeType,
-) -> Vec<u8> {
+) -> RgbaFloatBuffer {
     match resize_type {
+        ResizeType::Area => scaling_area(image, new_width, new_height),
         ResizeType::Bicubic => scaling_bicubic(image, new_width, new_height),
         ResizeType::Bilinear => scaling_bilinear(image, new_width, new_height),
+        ResizeType::CatmullRom => scaling_filter(
+            image,
+            new_width,
+            new_height,
+            kernel_catmull_rom,
+            CUBIC_SUPPORT,
+        ),
+        ResizeType::Lanczos3 => scaling_filter(
+            image,
+            new_width,
+            new_height,
+            kernel_lanczos3,
+            LANCZOS_SUPPORT,
+        ),
+        ResizeType::Mitchell => {
+            scaling_filter(image, new_width, new_height, kernel_mitchell, CUBIC_SUPPORT)
+        }
         ResizeType::NearestNeighbour => scaling_nearest_neighbour(image, new_width, new_height),
     }
 }
@@ -0,0 +1,121 @@
+use crate::coloring::{CellColor, create_color_runs};
+use crate::html_export::{TextStyle, contrast_color, escape_html, hex_color};
+
+// the baseline below the top of the font size, about the ascent of most monospace fonts
+const SVG_ASCENT: f32 = 0.8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// The advance of a char as multiple of the font size.
+    pub char_width: f32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self { char_width: 0.6 }
+    }
+}
+
+// two decimals are enough for pixels and keep the file small
+fn svg_number(value: f32) -> String {
+    let number = format!("{:.2}", value);
+    number
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// the text is stretched to its cells, so every font has the width of the grid
+fn svg_text(x: f32, y: f32, width: f32, fill: Option<[u8; 3]>, text: &str) -> String {
+    let fill = fill
+        .map(|rgb| format!(" fill=\"{}\"", hex_color(rgb)))
+        .unwrap_or_default();
+    format!(
+        "<text x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\"{}>{}</text>\n",
+        svg_number(x),
+        svg_number(y),
+        svg_number(width),
+        fill,
+        escape_html(text)
+    )
+}
+
+/// Writes the ascii image as SVG with positioned `<text>` elements, one per line or,
+/// with colors, one per run of chars with the same colors. Backgrounds are `<rect>`
+/// elements behind the text.
+pub fn create_svg(
+    text: &str,
+    colors: Option<&[CellColor]>,
+    style: &TextStyle,
+    options: &SvgOptions,
+) -> String {
+    let cell_w = style.font_size * options.char_width;
+    let cell_h = style.font_size * style.line_height;
+    let baseline = (cell_h - style.font_size) / 2. + style.font_size * SVG_ASCENT;
+    let cols = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let rows = text.lines().count();
+    let (width, height) = (cols as f32 * cell_w, rows as f32 * cell_h);
+
+    let mut svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = svg_number(width),
+        h = svg_number(height)
+    );
+    svg.push_str(&format!(
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        hex_color(style.background)
+    ));
+    svg.push_str(&format!(
+        "<g font-family=\"{}\" font-size=\"{}\" fill=\"{}\" xml:space=\"preserve\">\n",
+        escape_html(&style.font_family),
+        svg_number(style.font_size),
+        hex_color(contrast_color(style.background))
+    ));
+
+    match colors {
+        Some(colors) => {
+            let lines = create_color_runs(text, colors);
+            for (row, runs) in lines.iter().enumerate() {
+                for run in runs.iter() {
+                    if let Some(bg) = run.color.and_then(|color| color.bg) {
+                        svg.push_str(&format!(
+                            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                            svg_number(run.col as f32 * cell_w),
+                            svg_number(row as f32 * cell_h),
+                            svg_number(run.text.chars().count() as f32 * cell_w),
+                            svg_number(cell_h),
+                            hex_color(bg.rgb)
+                        ));
+                    }
+                }
+            }
+            for (row, runs) in lines.iter().enumerate() {
+                for run in runs.iter().filter(|run| !run.text.trim().is_empty()) {
+                    svg.push_str(&svg_text(
+                        run.col as f32 * cell_w,
+                        row as f32 * cell_h + baseline,
+                        run.text.chars().count() as f32 * cell_w,
+                        run.color.map(|color| color.fg.rgb),
+                        &run.text,
+                    ));
+                }
+            }
+        }
+        None => {
+            for (row, line) in text.lines().enumerate() {
+                if !line.trim().is_empty() {
+                    svg.push_str(&svg_text(
+                        0.,
+                        row as f32 * cell_h + baseline,
+                        line.chars().count() as f32 * cell_w,
+                        None,
+                        line,
+                    ));
+                }
+            }
+        }
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
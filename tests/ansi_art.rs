@@ -0,0 +1,304 @@
+use image::{DynamicImage, ImageBuffer, Luma};
+use img2asc::Converter;
+use img2asc::ansi_art::{
+    AnsOptions, AnsiArt, create_ans, load_ansi_art, parse_ansi_art, parse_sauce_record,
+};
+use img2asc::ascii_image::CellFit;
+use img2asc::coloring::{
+    AnsiColor, CellColor, create_ansi_text, create_color_runs, create_palette_colors,
+};
+use img2asc::palette::{ColorSpace, PALETTE_VGA, PALETTE_XTERM, Palette};
+use std::fs;
+
+// the palette index in every channel, to tell the colors apart
+fn test_palette() -> [[u8; 3]; 16] {
+    std::array::from_fn(|n| [n as u8 * 10; 3])
+}
+
+fn parse(data: &[u8]) -> AnsiArt {
+    parse_ansi_art(data, Some(&test_palette()))
+}
+
+fn ramp_image(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageLuma8(ImageBuffer::from_fn(width, height, |x, _| {
+        Luma([(x * 255 / width) as u8])
+    }))
+}
+
+fn vga(index: u8) -> AnsiColor {
+    AnsiColor {
+        rgb: PALETTE_VGA[index as usize],
+        index: Some(index),
+    }
+}
+
+#[test]
+fn cursor_moves_place_the_chars() {
+    let art = parse(b"AB\x1b[2DC\x1b[2BD\x1b[3;5HE\x1b[sF\x1b[1;1HG\x1b[uH\x1b[AI");
+    assert_eq!(art.text, "GB     \n      I\n D  EH \n");
+    assert_eq!((art.cols, art.rows), (7, 3));
+}
+
+#[test]
+fn code_page_437_wraps_at_80_columns() {
+    // a line break after a full line doesn't add an empty line
+    let mut data = vec![0xdb; 80];
+    data.extend_from_slice(b"\r\nx");
+    let art = parse(&data);
+    assert_eq!((art.cols, art.rows), (80, 2));
+    assert_eq!(
+        art.text,
+        format!("{}\nx{}\n", "█".repeat(80), " ".repeat(79))
+    );
+
+    let mut data = vec![b'a'; 81];
+    data.push(0xb0);
+    let art = parse(&data);
+    assert_eq!(
+        art.text,
+        format!("{}\na░{}\n", "a".repeat(80), " ".repeat(78))
+    );
+}
+
+#[test]
+fn graphic_renditions_set_the_colors() {
+    let palette = test_palette();
+    let indexed = |n: u8| AnsiColor {
+        rgb: palette[n as usize],
+        index: Some(n),
+    };
+    let art = parse(b"\x1b[31mR\x1b[0m\x1b[1;34mB\x1b[44;38;2;1;2;3mT\x1b[7mV");
+    let colors: Vec<(AnsiColor, Option<AnsiColor>)> = art
+        .colors
+        .iter()
+        .map(|color| (color.fg, color.bg))
+        .collect();
+    assert_eq!(
+        colors,
+        vec![
+            (indexed(1), None),
+            (indexed(12), None),
+            (AnsiColor::rgb([1, 2, 3]), Some(indexed(4))),
+            (indexed(4), Some(AnsiColor::rgb([1, 2, 3]))),
+        ]
+    );
+
+    // code page 437 has a black background, 256 colors are the xterm colors
+    let art = parse(b"\xb0\x1b[41;38;5;196m\xb1");
+    assert_eq!(art.colors[0].fg, indexed(7));
+    assert_eq!(art.colors[0].bg, Some(indexed(0)));
+    assert_eq!(
+        art.colors[1].fg,
+        AnsiColor {
+            rgb: [255, 0, 0],
+            index: Some(196)
+        }
+    );
+    assert_eq!(art.colors[1].bg, Some(indexed(1)));
+}
+
+#[test]
+fn palette_indexes_survive_the_round_trip() {
+    // code page 437 with the xterm palette, its grey 7 is nearer to the VGA white 15,
+    // the index stays anyway
+    let art = parse_ansi_art(
+        b"\x1b[0;37m\xdb\x1b[1;31m\xdb\x1b[0;44m\xb0",
+        Some(&PALETTE_XTERM),
+    );
+    let fg: Vec<Option<u8>> = art.colors.iter().map(|c| c.fg.index).collect();
+    assert_eq!(fg[..3], [Some(7), Some(9), Some(7)]);
+
+    let ans = create_ans(
+        &art.text,
+        Some(&art.colors),
+        art.cols,
+        &AnsOptions::default(),
+    );
+    let vga_art = parse_ansi_art(&ans, None);
+    assert_eq!(vga_art.text, art.text);
+    for (color, expected) in vga_art.colors.iter().zip(&art.colors) {
+        assert_eq!(color.fg.index, expected.fg.index);
+        assert_eq!(color.fg.rgb, PALETTE_VGA[color.fg.index.unwrap() as usize]);
+        assert_eq!(
+            color.bg.and_then(|bg| bg.index),
+            expected.bg.and_then(|bg| bg.index)
+        );
+    }
+
+    // the same palette keeps the indexes, another one gets the nearest colors
+    let xterm = create_palette_colors(
+        &art.colors,
+        &Palette::ansi16(&PALETTE_XTERM, ColorSpace::Oklab),
+    );
+    assert_eq!(xterm, art.colors);
+    let vga = create_palette_colors(
+        &art.colors,
+        &Palette::ansi16(&PALETTE_VGA, ColorSpace::Oklab),
+    );
+    assert_eq!(vga[0].fg.index, Some(15));
+}
+
+#[test]
+fn huge_cursor_moves_are_clamped() {
+    // the cursor stops below the last row, the chars there are left out
+    let art = parse(b"A\x1b[4000000000BB");
+    assert_eq!((art.text.as_str(), art.rows), ("A\n", 1));
+    let art = parse(b"A\x1b[4000000000;4000000000HB\x1b[4000000000AC");
+    assert_eq!(art.rows, 1);
+    assert!(art.text.starts_with('A') && art.text.trim_end().ends_with('C'));
+
+    // the cursor stops at the last column of code page 437
+    let art = parse(b"\xb0\x1b[99999999CX");
+    assert_eq!((art.cols, art.rows), (80, 1));
+    assert_eq!(art.text.chars().nth(79), Some('X'));
+
+    // and at the last column of the screen for UTF-8
+    let art = parse(b"\x1b[99999999CX\x1b[99999999DY");
+    assert_eq!((art.cols, art.rows), (1000, 1));
+    assert!(art.text.starts_with('Y') && art.text.ends_with("X\n"));
+}
+
+#[test]
+fn sauce_width_is_clamped() {
+    let options = AnsOptions::default();
+    let ans = create_ans("ab\n", None, 60000, &options);
+    let art = parse(&ans);
+    assert_eq!(art.sauce.map(|sauce| sauce.cols), Some(60000));
+    assert_eq!((art.cols, art.rows), (1000, 1));
+}
+
+#[test]
+fn ans_files_keep_text_colors_and_sauce() {
+    let text = "ab█\n░▒▓\n";
+    let colors: Vec<CellColor> = [(1, 0), (9, 4), (14, 2), (7, 0), (15, 1), (3, 6)]
+        .iter()
+        .map(|(fg, bg)| CellColor {
+            fg: vga(*fg),
+            bg: Some(vga(*bg)),
+        })
+        .collect();
+    let options = AnsOptions {
+        title: "Round trip".to_string(),
+        author: "img2asc".to_string(),
+        group: "tests".to_string(),
+        date: "20261017".to_string(),
+        font: "IBM VGA".to_string(),
+        ice_colors: false,
+    };
+
+    let filename = std::env::temp_dir().join(format!("img2asc_{}.ans", std::process::id()));
+    fs::write(&filename, create_ans(text, Some(&colors), 3, &options)).unwrap();
+    let art = load_ansi_art(filename.to_str().unwrap(), None);
+    fs::remove_file(&filename).unwrap();
+    let art = art.unwrap();
+
+    let sauce = art.sauce.unwrap();
+    assert_eq!(sauce.options, options);
+    assert_eq!((sauce.cols, sauce.rows), (3, 2));
+    assert_eq!((art.cols, art.rows), (3, 2));
+    assert_eq!(art.text, text);
+    for (color, expected) in art.colors.iter().zip(colors) {
+        assert_eq!(color.fg.rgb, expected.fg.rgb);
+        assert_eq!(color.bg.map(|bg| bg.rgb), expected.bg.map(|bg| bg.rgb));
+    }
+}
+
+#[test]
+fn ans_lines_end_with_a_line_break() {
+    let line = "#".repeat(30);
+    let text = format!("{}\n{}\n{}\n", line, line, line);
+    let ans = create_ans(&text, None, 30, &AnsOptions::default());
+    let end = ans.iter().position(|b| *b == 0x1a).unwrap();
+    assert_eq!(ans[..end], text.replace('\n', "\r\n").into_bytes());
+
+    // viewers without SAUCE wrap at 80 columns, the rows stay apart anyway
+    let art = parse(&ans[..end]);
+    assert_eq!(art.rows, 3);
+    for row in art.text.lines() {
+        assert_eq!(row.trim_end(), line);
+    }
+    // with SAUCE the full lines don't add empty rows
+    assert_eq!(parse(&ans).text, text);
+}
+
+#[test]
+fn sauce_size_is_the_size_of_the_text() {
+    for fit in [CellFit::Pad, CellFit::Crop, CellFit::Stretch] {
+        for (width, height) in [(0, 10), (27, 0), (27, 10)] {
+            let mut converter = Converter::new();
+            converter.cell_fit = fit;
+            converter.ascii_width = width;
+            converter.ascii_height = height;
+            let conversion = converter.convert(&ramp_image(64, 32)).unwrap();
+
+            let ans = create_ans(
+                &conversion.text,
+                None,
+                conversion.cols,
+                &AnsOptions::default(),
+            );
+            let sauce = parse_sauce_record(&ans).unwrap();
+            let widths: Vec<u32> = conversion
+                .text
+                .lines()
+                .map(|line| line.chars().count() as u32)
+                .collect();
+
+            assert_eq!(
+                sauce.rows,
+                widths.len() as u32,
+                "{:?} {}x{}",
+                fit,
+                width,
+                height
+            );
+            assert!(
+                widths.iter().all(|w| *w == sauce.cols),
+                "{:?} {}x{}: {} cols for lines of {:?}",
+                fit,
+                width,
+                height,
+                sauce.cols,
+                widths
+            );
+        }
+    }
+}
+
+#[test]
+fn default_background_ends_the_background_run() {
+    // "AB" on red, then two spaces on the default background
+    let art = parse_ansi_art(b"\x1b[41mAB\x1b[49m  \n", None);
+    assert_eq!(art.text, "AB  \n");
+    assert!(art.colors[1].bg.is_some());
+    assert_eq!(art.colors[2].bg, None);
+
+    let text = parse_ansi_art(create_ansi_text(&art.text, &art.colors).as_bytes(), None);
+    let backgrounds: Vec<bool> = text.colors.iter().map(|c| c.bg.is_some()).collect();
+    assert_eq!(backgrounds, [true, true, false, false]);
+
+    let runs = &create_color_runs(&art.text, &art.colors)[0];
+    assert_eq!(runs.len(), 2);
+    assert_eq!((runs[1].col, runs[1].text.as_str()), (2, "  "));
+    assert_eq!(runs[1].color.and_then(|color| color.bg), None);
+
+    let ans = parse_ansi_art(
+        &create_ans(
+            &art.text,
+            Some(&art.colors),
+            art.cols,
+            &AnsOptions::default(),
+        ),
+        None,
+    );
+    let backgrounds: Vec<[u8; 3]> = ans.colors.iter().map(|c| c.bg.unwrap().rgb).collect();
+    assert_eq!(
+        backgrounds,
+        [
+            PALETTE_VGA[1],
+            PALETTE_VGA[1],
+            PALETTE_VGA[0],
+            PALETTE_VGA[0]
+        ]
+    );
+}
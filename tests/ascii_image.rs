@@ -0,0 +1,232 @@
+use image::{DynamicImage, ImageBuffer, Luma};
+use img2asc::ascii_image::{
+    ASCII_RAMP_NAMES, AsciiType, RampOrder, braille_char, create_font_ramp, get_named_ramp,
+    parse_ramp, quadrant_char, sextant_char,
+};
+use img2asc::bitmap_font::BitmapFont;
+use img2asc::resizing::ResizeType;
+use img2asc::{Converter, Img2AscError};
+
+// a 4x4 font with 16, 8, 4 and 2 pixels of ink for '#', '+', ':' and '.', no space
+const RAMP_BDF: &str = "STARTFONT 2.1
+FONTBOUNDINGBOX 4 4 0 0
+STARTCHAR hash
+ENCODING 35
+BBX 4 4 0 0
+BITMAP
+F0
+F0
+F0
+F0
+ENDCHAR
+STARTCHAR plus
+ENCODING 43
+BBX 4 4 0 0
+BITMAP
+F0
+00
+F0
+00
+ENDCHAR
+STARTCHAR colon
+ENCODING 58
+BBX 4 4 0 0
+BITMAP
+60
+00
+60
+00
+ENDCHAR
+STARTCHAR period
+ENCODING 46
+BBX 4 4 0 0
+BITMAP
+00
+00
+00
+60
+ENDCHAR
+ENDFONT
+";
+
+// one pixel per char from black to white
+const GREYS: [u8; 5] = [0, 64, 128, 191, 255];
+
+fn grey_ramp(ramp: &str, invert: bool) -> Result<String, Img2AscError> {
+    let image =
+        DynamicImage::ImageLuma8(ImageBuffer::from_fn(5, 1, |x, _| Luma([GREYS[x as usize]])));
+    let mut converter = Converter::new();
+    converter.ascii_type = AsciiType::Custom;
+    converter.charset = parse_ramp(ramp);
+    converter.ascii_width = 5;
+    converter.ascii_height = 1;
+    converter.resize_opt = ResizeType::NearestNeighbour;
+    converter.linear = false;
+    converter.invert = invert;
+    Ok(converter.convert(&image)?.text)
+}
+
+// the Unicode dot numbers of a braille cell, row by row
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[1, 4], [2, 5], [3, 6], [7, 8]];
+
+#[test]
+fn braille_dots_follow_the_pixels() {
+    for bits in 0..256 {
+        let mut expected = 0x2800;
+        for (y, row) in BRAILLE_DOTS.iter().enumerate() {
+            for (x, dot) in row.iter().enumerate() {
+                if bits >> (y * 2 + x) & 1 != 0 {
+                    expected += 1 << (dot - 1);
+                }
+            }
+        }
+        assert_eq!(
+            braille_char(bits),
+            char::from_u32(expected).unwrap(),
+            "{:08b}",
+            bits
+        );
+    }
+
+    assert_eq!(braille_char(0), '⠀');
+    assert_eq!(braille_char(0b0000_0010), '⠈');
+    assert_eq!(braille_char(0b0101_0101), '⡇');
+    assert_eq!(braille_char(0b1010_1010), '⢸');
+    assert_eq!(braille_char(0b1111_1111), '⣿');
+}
+
+#[test]
+fn quadrants_follow_the_pixels() {
+    assert_eq!(quadrant_char(0b0000), ' ');
+    assert_eq!(quadrant_char(0b0001), '▘');
+    assert_eq!(quadrant_char(0b0010), '▝');
+    assert_eq!(quadrant_char(0b0100), '▖');
+    assert_eq!(quadrant_char(0b1000), '▗');
+    assert_eq!(quadrant_char(0b0011), '▀');
+    assert_eq!(quadrant_char(0b0101), '▌');
+    assert_eq!(quadrant_char(0b1010), '▐');
+    assert_eq!(quadrant_char(0b0110), '▞');
+    assert_eq!(quadrant_char(0b1110), '▟');
+    assert_eq!(quadrant_char(0b1111), '█');
+}
+
+#[test]
+fn sextants_skip_the_half_blocks() {
+    assert_eq!(sextant_char(0), ' ');
+    assert_eq!(sextant_char(0b000001), '🬀');
+    assert_eq!(sextant_char(0b010100), '🬓');
+    // full left and full right are the existing half blocks
+    assert_eq!(sextant_char(0b010101), '▌');
+    assert_eq!(sextant_char(0b010110), '🬔');
+    assert_eq!(sextant_char(0b101001), '🬧');
+    assert_eq!(sextant_char(0b101010), '▐');
+    assert_eq!(sextant_char(0b101011), '🬨');
+    assert_eq!(sextant_char(0b111110), '🬻');
+    assert_eq!(sextant_char(0b111111), '█');
+
+    let mut chars: Vec<char> = (0..64).map(sextant_char).collect();
+    chars.sort();
+    chars.dedup();
+    assert_eq!(chars.len(), 64);
+}
+
+#[test]
+fn named_ramps_go_from_black_to_white() {
+    for name in ASCII_RAMP_NAMES {
+        let ramp = get_named_ramp(name).unwrap();
+        assert!(ramp.len() >= 2, "{}", name);
+        assert_eq!(ramp.last(), Some(&' '), "{}", name);
+    }
+    assert_eq!(get_named_ramp("SIMPLE"), get_named_ramp("simple"));
+    assert_eq!(get_named_ramp("dot"), Some(vec!['.', ' ']));
+    assert_eq!(get_named_ramp("shades"), None);
+}
+
+#[test]
+fn ramps_are_split_into_chars() {
+    assert_eq!(parse_ramp("@#\r\n. \n"), ['@', '#', '.', ' ']);
+    assert_eq!(parse_ramp("█▓▒░ "), ['█', '▓', '▒', '░', ' ']);
+    assert_eq!(parse_ramp("🌑🌓🌕"), ['🌑', '🌓', '🌕']);
+    // a combining accent is a char of its own
+    assert_eq!(parse_ramp("e\u{301}."), ['e', '\u{301}', '.']);
+    assert!(parse_ramp("\n").is_empty());
+}
+
+#[test]
+fn custom_ramps_map_black_to_the_first_char() {
+    assert_eq!(grey_ramp("@#+. ", false).unwrap(), "@#+. \n");
+    assert_eq!(grey_ramp("@#+. ", true).unwrap(), " .+#@\n");
+    assert_eq!(grey_ramp("█▓▒░ ", false).unwrap(), "█▓▒░ \n");
+    // the middle grey 128 is just above the middle of two chars, inverted 127 just below
+    assert_eq!(grey_ramp("#.", false).unwrap(), "##...\n");
+    assert_eq!(grey_ramp("#.", true).unwrap(), "..###\n");
+}
+
+#[test]
+fn custom_ramps_need_two_chars() {
+    for ramp in ["", "@", "\n"] {
+        assert!(
+            matches!(
+                grey_ramp(ramp, false),
+                Err(Img2AscError::InvalidOption { .. })
+            ),
+            "{:?}",
+            ramp
+        );
+    }
+}
+
+#[test]
+fn font_ramps_are_sorted_by_coverage() {
+    let font = BitmapFont::parse_bdf(RAMP_BDF).unwrap();
+    // 'x' has no glyph, the space never has ink, duplicates are left out
+    let ramp = parse_ramp(". x:#+ .#");
+    assert_eq!(
+        create_font_ramp(&ramp, &font, false),
+        ['#', '+', ':', '.', ' ']
+    );
+    assert_eq!(create_font_ramp(&parse_ramp("x."), &font, false), ['.']);
+    assert!(create_font_ramp(&parse_ramp("xy"), &font, false).is_empty());
+}
+
+#[test]
+fn even_font_ramps_have_even_lightness_steps() {
+    let font = BitmapFont::parse_bdf(RAMP_BDF).unwrap();
+    // the lightness of the uncovered part is 0, 76, 89, 95 and 100, so the dark steps
+    // repeat '#' and '+' and the light chars are left out
+    let ramp = parse_ramp("#+:. ");
+    assert_eq!(
+        create_font_ramp(&ramp, &font, true),
+        ['#', '#', '+', '+', ' ']
+    );
+    // two chars are already even
+    assert_eq!(create_font_ramp(&parse_ramp(". "), &font, true), ['.', ' ']);
+    assert_eq!(create_font_ramp(&parse_ramp("."), &font, true), ['.']);
+}
+
+#[test]
+fn ramp_order_needs_a_font_only_for_ramps() {
+    let image = DynamicImage::ImageLuma8(ImageBuffer::from_fn(16, 16, |x, y| {
+        Luma([(x * 16 + y) as u8])
+    }));
+    let mut converter = Converter::new();
+    converter.ascii_width = 8;
+    converter.ramp_order = RampOrder::Coverage;
+    for ascii_type in [
+        AsciiType::Braille,
+        AsciiType::HalfBlock,
+        AsciiType::Quadrant,
+        AsciiType::Sextant,
+    ] {
+        converter.ascii_type = ascii_type;
+        assert!(converter.convert(&image).is_ok(), "{:?}", ascii_type);
+    }
+
+    converter.ascii_type = AsciiType::Simple;
+    assert!(matches!(
+        converter.convert(&image),
+        Err(Img2AscError::MissingArgument(_))
+    ));
+    converter.font = Some(BitmapFont::embedded());
+    assert!(converter.convert(&image).is_ok());
+}
@@ -0,0 +1,220 @@
+use img2asc::Img2AscError;
+use img2asc::bitmap_font::BitmapFont;
+
+// a 4x6 font with a full block and a 2x2 dot in the lower right corner
+const BDF: &str = "STARTFONT 2.1
+FONT test
+FONTBOUNDINGBOX 4 6 0 -1
+CHARS 2
+STARTCHAR block
+ENCODING 9608
+BBX 4 6 0 -1
+BITMAP
+F0
+F0
+F0
+F0
+F0
+F0
+ENDCHAR
+STARTCHAR period
+ENCODING 46
+BBX 2 2 2 -1
+BITMAP
+C0
+C0
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+BBX 4 6 0 -1
+BITMAP
+F0
+ENDCHAR
+ENDFONT
+";
+
+// glyph n has its row n set
+fn psf_glyphs(count: usize, row_bytes: usize, height: usize) -> Vec<u8> {
+    (0..count * height)
+        .flat_map(|n| {
+            let value = if n % height == n / height % height {
+                0xff
+            } else {
+                0
+            };
+            vec![value; row_bytes]
+        })
+        .collect()
+}
+
+fn psf1(mode: u8, height: u8, table: &[u16]) -> Vec<u8> {
+    let count = if mode & 0x01 != 0 { 512 } else { 256 };
+    let mut data = vec![0x36, 0x04, mode, height];
+    data.extend(psf_glyphs(count, 1, height as usize));
+    data.extend(table.iter().flat_map(|value| value.to_le_bytes()));
+    data
+}
+
+// the table has the UTF-8 chars of every glyph followed by 0xff
+fn psf2(width: u32, height: u32, length: u32, table: Option<&[u8]>) -> Vec<u8> {
+    let flags = table.is_some() as u32;
+    let glyph_bytes = width.div_ceil(8) * height;
+    let mut data = vec![0x72, 0xb5, 0x4a, 0x86];
+    for value in [0, 32, flags, length, glyph_bytes, height, width] {
+        data.extend(u32::to_le_bytes(value));
+    }
+    data.extend(psf_glyphs(
+        length as usize,
+        width.div_ceil(8) as usize,
+        height as usize,
+    ));
+    data.extend(table.unwrap_or_default());
+    data
+}
+
+fn assert_invalid(result: Result<BitmapFont, Img2AscError>, what: &str) {
+    assert!(
+        matches!(result, Err(Img2AscError::InvalidFont(_))),
+        "{}: {:?}",
+        what,
+        result.map(|font| (font.width(), font.height()))
+    );
+}
+
+#[test]
+fn bdf_glyphs_are_placed_in_the_bounding_box() {
+    let font = BitmapFont::parse_bdf(BDF).unwrap();
+    assert_eq!((font.width(), font.height()), (4, 6));
+    assert_eq!(font.coverage('█'), Some(1.));
+
+    // the dot sits on the baseline in the two right columns
+    assert_eq!(font.coverage('.'), Some(4. / 24.));
+    for y in 0..6 {
+        for x in 0..4 {
+            assert_eq!(font.get('.', x, y), x >= 2 && y >= 4, "{} {}", x, y);
+        }
+    }
+
+    assert!(!font.has_glyph('a'));
+    assert_eq!(font.chars().count(), 2);
+}
+
+#[test]
+fn malformed_bdf_fonts_are_rejected() {
+    let missing_box = BDF.replace("FONTBOUNDINGBOX 4 6 0 -1\n", "");
+    let fonts = [
+        ("missing bounding box", missing_box),
+        (
+            "huge bounding box",
+            BDF.replace(
+                "FONTBOUNDINGBOX 4 6 0 -1",
+                "FONTBOUNDINGBOX 100000 100000 0 0",
+            ),
+        ),
+        (
+            "empty bounding box",
+            BDF.replace("FONTBOUNDINGBOX 4 6 0 -1", "FONTBOUNDINGBOX 0 6 0 0"),
+        ),
+        (
+            "short bounding box",
+            BDF.replace("FONTBOUNDINGBOX 4 6 0 -1", "FONTBOUNDINGBOX 4 6"),
+        ),
+        (
+            "huge glyph offset",
+            BDF.replace("BBX 2 2 2 -1", "BBX 2 2 2147483647 -1"),
+        ),
+        (
+            "negative glyph",
+            BDF.replace("BBX 2 2 2 -1", "BBX -2 2 2 -1"),
+        ),
+        ("bad hex", BDF.replace("C0\nC0", "C0\nXY")),
+        (
+            "no glyphs",
+            BDF.replace("ENCODING 9608", "ENCODING -1")
+                .replace("ENCODING 46", "ENCODING -1"),
+        ),
+    ];
+    for (what, font) in fonts {
+        assert_invalid(BitmapFont::parse_bdf(&font), what);
+    }
+}
+
+#[test]
+fn psf1_without_table_has_ascii_glyphs() {
+    let font = BitmapFont::parse_psf(&psf1(0, 8, &[])).unwrap();
+    assert_eq!((font.width(), font.height()), (8, 8));
+    assert_eq!(font.chars().count(), 128);
+
+    // 'A' is glyph 65, its row 1 is set
+    for y in 0..8 {
+        assert_eq!(font.get('A', 0, y), y == 1);
+    }
+}
+
+#[test]
+fn psf1_table_maps_glyphs_to_unicode() {
+    // glyph 0 is 'x' and 'y', glyph 1 is '█' with a skipped sequence
+    let table = [
+        'x' as u16, 'y' as u16, 0xffff, 0x2588, 0xfffe, 'a' as u16, 0x301, 0xffff,
+    ];
+    let font = BitmapFont::parse_psf(&psf1(0x03, 16, &table)).unwrap();
+    assert_eq!((font.width(), font.height()), (8, 16));
+    assert!(font.get('x', 3, 0) && font.get('y', 3, 0));
+    assert!(font.get('█', 3, 1));
+    assert!(!font.has_glyph('a'));
+}
+
+#[test]
+fn psf2_table_maps_glyphs_to_unicode() {
+    // 'é' is followed by the sequence 'e' and a combining accent after 0xfe
+    let table = [
+        b"A\xff".as_slice(),
+        "é".as_bytes(),
+        b"\xfee\xcc\x81\xff",
+        "▒".as_bytes(),
+        b"\xff",
+    ]
+    .concat();
+    let font = BitmapFont::parse_psf(&psf2(10, 12, 3, Some(&table))).unwrap();
+    assert_eq!((font.width(), font.height()), (10, 12));
+    assert!(font.get('A', 9, 0));
+    assert!(font.get('é', 0, 1) && !font.get('é', 0, 0));
+    assert!(font.get('▒', 7, 2));
+    assert!(!font.has_glyph('e'));
+}
+
+#[test]
+fn malformed_psf_fonts_are_rejected() {
+    let mut truncated = psf2(8, 8, 4, None);
+    truncated.truncate(40);
+    let fonts = [
+        ("no magic", vec![0; 64]),
+        ("short psf1 header", vec![0x36, 0x04, 0]),
+        ("short psf2 header", vec![0x72, 0xb5, 0x4a, 0x86, 0, 0]),
+        ("psf1 height 0", psf1(0, 0, &[])),
+        ("psf2 width 0", psf2(0, 8, 4, None)),
+        ("psf2 height 0", psf2(8, 0, 4, None)),
+        ("psf2 no glyphs", psf2(8, 8, 0, None)),
+        ("psf2 huge width", psf2(100000, 1, 0, None)),
+        ("truncated glyphs", truncated),
+    ];
+    for (what, font) in fonts {
+        assert_invalid(BitmapFont::parse_psf(&font), what);
+    }
+
+    // a glyph count that overflows the glyph data size
+    let mut huge = psf2(8, 8, 1, None);
+    huge[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_invalid(BitmapFont::parse_psf(&huge), "huge glyph count");
+}
+
+#[test]
+fn embedded_font_has_every_printable_ascii_char() {
+    let font = BitmapFont::embedded();
+    assert_eq!((font.width(), font.height()), (6, 12));
+    for c in ' '..='~' {
+        assert!(font.has_glyph(c), "{:?}", c);
+    }
+    assert_eq!(font.coverage(' '), Some(0.));
+    assert_eq!(font.coverage('█'), Some(1.));
+}
@@ -0,0 +1,123 @@
+use image::{DynamicImage, ImageBuffer, Luma};
+use img2asc::ascii_image::{AsciiType, CellFit};
+use img2asc::{Converter, calc_image_size};
+
+const FITS: [CellFit; 3] = [CellFit::Pad, CellFit::Crop, CellFit::Stretch];
+const TYPES: [AsciiType; 4] = [
+    AsciiType::Simple,
+    AsciiType::Braille,
+    AsciiType::Quadrant,
+    AsciiType::Sextant,
+];
+// width only, height only and both
+const SIZES: [(u16, u16); 3] = [(40, 0), (0, 10), (40, 10)];
+
+fn image(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageLuma8(ImageBuffer::from_fn(width, height, |x, y| {
+        Luma([((x + y) % 256) as u8])
+    }))
+}
+
+fn converter(fit: CellFit, ascii_type: AsciiType, (width, height): (u16, u16)) -> Converter {
+    let mut converter = Converter::new();
+    converter.cell_fit = fit;
+    converter.ascii_type = ascii_type;
+    converter.ascii_width = width;
+    converter.ascii_height = height;
+    converter
+}
+
+#[test]
+fn ascii_size_is_the_size_of_the_conversion() {
+    for (img_width, img_height) in [(200, 100), (100, 200), (123, 77), (77, 123)] {
+        let image = image(img_width, img_height);
+        for fit in FITS {
+            for ascii_type in TYPES {
+                for size in SIZES {
+                    let converter = converter(fit, ascii_type, size);
+                    let (cols, rows) = converter.ascii_size(img_width, img_height).unwrap();
+                    let conversion = converter.convert(&image).unwrap();
+                    let what = format!(
+                        "{:?} {:?} {:?} on {}x{}",
+                        fit, ascii_type, size, img_width, img_height
+                    );
+
+                    assert_eq!((conversion.cols, conversion.rows), (cols, rows), "{}", what);
+                    assert_eq!(conversion.text.lines().count() as u32, rows, "{}", what);
+                    for line in conversion.text.lines() {
+                        assert_eq!(line.chars().count() as u32, cols, "{}", what);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn free_side_follows_the_aspect_ratio() {
+    // a char is half as wide as high, so 200x100 pixels are 40x10 chars
+    let expected = [
+        ((200, 100), [(40, 10), (40, 10), (40, 10)]),
+        ((100, 200), [(40, 40), (10, 10), (40, 10)]),
+    ];
+    for ((img_width, img_height), sizes) in expected {
+        for fit in FITS {
+            for ascii_type in TYPES {
+                for (size, expected) in SIZES.iter().zip(sizes) {
+                    let converter = converter(fit, ascii_type, *size);
+                    assert_eq!(
+                        converter.ascii_size(img_width, img_height).unwrap(),
+                        expected,
+                        "{:?} {:?} {:?} on {}x{}",
+                        fit,
+                        ascii_type,
+                        size,
+                        img_width,
+                        img_height
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn crop_leaves_out_partial_cells_and_pad_keeps_them() {
+    // 20 cols of braille are 40 pixels, so the 123x77 image is 25 pixels or 6.25 rows high
+    let size = (20, 0);
+    let crop = converter(CellFit::Crop, AsciiType::Braille, size);
+    let pad = converter(CellFit::Pad, AsciiType::Braille, size);
+    assert_eq!(crop.ascii_size(123, 77).unwrap(), (20, 6));
+    assert_eq!(pad.ascii_size(123, 77).unwrap(), (20, 7));
+}
+
+#[test]
+fn glyph_size_needs_a_font() {
+    let converter = converter(CellFit::Pad, AsciiType::Glyph, (40, 0));
+    assert!(converter.ascii_size(200, 100).is_err());
+}
+
+#[test]
+fn calc_image_size_keeps_the_aspect_ratio() {
+    // landscape and portrait with only the width, only the height and neither
+    let expected = [
+        ((200, 100), (40, 0), (40, 10)),
+        ((200, 100), (0, 10), (40, 10)),
+        ((200, 100), (0, 0), (80, 20)),
+        ((100, 200), (40, 0), (40, 40)),
+        ((100, 200), (0, 10), (10, 10)),
+        ((100, 200), (0, 0), (80, 80)),
+        ((100, 200), (30, 20), (30, 20)),
+    ];
+    for ((img_width, img_height), (width, height), size) in expected {
+        assert_eq!(
+            calc_image_size(img_width, img_height, width, height),
+            size,
+            "{}x{} with {}x{}",
+            img_width,
+            img_height,
+            width,
+            height
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use image::{DynamicImage, Rgb, RgbImage};
+use img2asc::Converter;
+use img2asc::ascii_image::{AsciiType, CellFit, CellGrid};
+use img2asc::coloring::{
+    AnsiColor, CellColor, ColorMode, create_ansi_text, create_cell_colors, create_half_block_colors,
+};
+use img2asc::pixel_buffer::RgbaFloatBuffer;
+
+const RED: [f32; 4] = [1., 0., 0., 1.];
+const BLUE: [f32; 4] = [0., 0., 1., 1.];
+
+fn fg(rgb: [u8; 3]) -> CellColor {
+    CellColor {
+        fg: AnsiColor::rgb(rgb),
+        bg: None,
+    }
+}
+
+fn indexed(index: u8) -> AnsiColor {
+    AnsiColor {
+        rgb: [0; 3],
+        index: Some(index),
+    }
+}
+
+// red on the left half and blue on the right half, or red above blue
+fn halves(width: u32, height: u32, vertical: bool) -> RgbaFloatBuffer {
+    let mut image = RgbaFloatBuffer::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let red = if vertical {
+                x < width / 2
+            } else {
+                y < height / 2
+            };
+            image.put_pixel(x, y, if red { RED } else { BLUE });
+        }
+    }
+    image
+}
+
+#[test]
+fn cells_get_their_average_color() {
+    let image = halves(4, 2, true);
+    let grid = CellGrid::new(4, 2, 2, 2, CellFit::Stretch);
+
+    let colors = create_cell_colors(&image, &grid, 30, false, false, false);
+    assert_eq!(colors, vec![fg([255, 0, 0]), fg([0, 0, 255])]);
+
+    // the background mode has black or, inverted, white ink
+    let colors = create_cell_colors(&image, &grid, 30, false, true, true);
+    assert_eq!(
+        colors[1],
+        CellColor {
+            fg: AnsiColor::rgb([255; 3]),
+            bg: Some(AnsiColor::rgb([0, 0, 255])),
+        }
+    );
+
+    // the average of red and blue in linear light is brighter than in sRGB
+    let grid = CellGrid::new(4, 2, 4, 2, CellFit::Stretch);
+    let srgb = create_cell_colors(&image, &grid, 30, false, false, false);
+    let linear = create_cell_colors(&image, &grid, 30, true, false, false);
+    assert_eq!(srgb[0].fg.rgb, [128, 0, 128]);
+    let [r, g, b] = linear[0].fg.rgb;
+    assert!(r > 180 && g == 0 && b == r, "{:?}", linear[0].fg.rgb);
+}
+
+#[test]
+fn half_blocks_have_the_upper_pixel_in_front() {
+    let image = halves(1, 2, false);
+    let grid = CellGrid::new(1, 2, 1, 2, CellFit::Stretch);
+    let colors = create_half_block_colors(&image, &grid, 30, false, false);
+    assert_eq!(
+        colors,
+        vec![CellColor {
+            fg: AnsiColor::rgb([255, 0, 0]),
+            bg: Some(AnsiColor::rgb([0, 0, 255])),
+        }]
+    );
+}
+
+#[test]
+fn ansi_text_writes_colors_only_on_changes() {
+    let colors = [
+        fg([1, 2, 3]),
+        fg([1, 2, 3]),
+        fg([9, 9, 9]),
+        fg([4, 5, 6]),
+        CellColor {
+            fg: indexed(1),
+            bg: Some(indexed(12)),
+        },
+        CellColor {
+            fg: indexed(200),
+            bg: None,
+        },
+    ];
+    let text = create_ansi_text("ab c\nde\n", &colors);
+    assert_eq!(
+        text,
+        "\x1b[38;2;1;2;3mab \x1b[38;2;4;5;6mc\x1b[0m\n\
+         \x1b[31;104md\x1b[38;5;200;49me\x1b[0m\n"
+    );
+}
+
+#[test]
+fn colored_conversion_keeps_the_chars() {
+    let image = DynamicImage::ImageRgb8(RgbImage::from_fn(32, 16, |x, _| {
+        if x < 16 {
+            Rgb([200, 30, 30])
+        } else {
+            Rgb([30, 30, 200])
+        }
+    }));
+    let mut converter = Converter::new();
+    converter.ascii_width = 8;
+    converter.ascii_height = 4;
+    let plain = converter.convert(&image).unwrap();
+    assert!(plain.colors.is_none());
+    assert_eq!(plain.ansi, plain.text);
+
+    converter.color = ColorMode::TrueColor;
+    let colored = converter.convert(&image).unwrap();
+    assert_eq!(colored.text, plain.text);
+    let colors = colored.colors.unwrap();
+    assert_eq!(colors.len(), 32);
+    assert!(colors[0].fg.rgb[0] > colors[0].fg.rgb[2]);
+    assert!(colors[7].fg.rgb[2] > colors[7].fg.rgb[0]);
+    assert_eq!(colored.ansi.lines().count(), 4);
+    assert!(colored.ansi.lines().all(|line| line.ends_with("\x1b[0m")));
+
+    // half blocks are always colored
+    converter.color = ColorMode::None;
+    converter.ascii_type = AsciiType::HalfBlock;
+    let half_block = converter.convert(&image).unwrap();
+    assert!(half_block.colors.is_some());
+    assert!(half_block.text.chars().all(|c| c == '▀' || c == '\n'));
+}
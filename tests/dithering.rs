@@ -0,0 +1,423 @@
+use image::{DynamicImage, ImageBuffer, Luma};
+use img2asc::coloring::{AnsiColor, CellColor, create_palette_dither_colors};
+use img2asc::dithering::{
+    DitherKernel, DitherOptions, Dithering, create_dither_image, create_level_dither_image,
+    create_palette_dither_image, get_threshold_map,
+};
+use img2asc::gamma::srgb_u8_to_linear;
+use img2asc::palette::{ColorSpace, PALETTE_XTERM, Palette};
+use img2asc::pixel_buffer::{BitmapBuffer, GreyBuffer, PixelBuffer, RgbaFloatBuffer};
+use img2asc::{Converter, Img2AscError};
+
+const SIZE: u32 = 128;
+const LEVELS: [u8; 7] = [16, 48, 96, 128, 160, 208, 240];
+
+const KERNELS: [Dithering; 7] = [
+    Dithering::Burkes,
+    Dithering::FloydSteinberg,
+    Dithering::Jjn,
+    Dithering::Sierra,
+    Dithering::SierraLite,
+    Dithering::Stucki,
+    Dithering::TwoRowSierra,
+];
+
+fn average(bitmap: &BitmapBuffer) -> f32 {
+    let sum: u32 = bitmap.to_bytes().iter().map(|v| *v as u32).sum();
+    sum as f32 / (bitmap.width() * bitmap.height()) as f32
+}
+
+fn flat(value: u8) -> GreyBuffer {
+    GreyBuffer::from_raw(SIZE, SIZE, vec![value; (SIZE * SIZE) as usize]).unwrap()
+}
+
+#[test]
+fn kernels_preserve_flat_grey() {
+    for kernel in KERNELS {
+        for level in LEVELS {
+            let bitmap = create_dither_image(&flat(level), 128, &DitherOptions::new(kernel, false));
+            let avg = average(&bitmap);
+            assert!(
+                (avg - level as f32).abs() < 3.,
+                "{:?} on {}: average {}",
+                kernel,
+                level,
+                avg
+            );
+        }
+    }
+}
+
+#[test]
+fn kernels_preserve_flat_grey_in_linear_light() {
+    for kernel in KERNELS {
+        for level in LEVELS {
+            let bitmap = create_dither_image(&flat(level), 128, &DitherOptions::new(kernel, true));
+            let avg = average(&bitmap);
+            let expected = srgb_u8_to_linear(level);
+            assert!(
+                (avg - expected).abs() < 3.,
+                "{:?} on {}: average {} instead of {}",
+                kernel,
+                level,
+                avg,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn kernels_preserve_ramp_columns() {
+    // every column of the ramp is flat, so every column band has to keep its value
+    let data: Vec<u8> = (0..SIZE).flat_map(|_| 0..=255).collect();
+    let ramp = GreyBuffer::from_raw(256, SIZE, data).unwrap();
+
+    for kernel in KERNELS {
+        let bitmap = create_dither_image(&ramp, 128, &DitherOptions::new(kernel, false));
+        for band in (0..256).step_by(32) {
+            let mut sum = 0;
+            for y in 0..bitmap.height() {
+                for x in band..band + 32 {
+                    if bitmap.get(x, y) {
+                        sum += 255;
+                    }
+                }
+            }
+            let avg = sum as f32 / (32 * bitmap.height()) as f32;
+            let expected = band as f32 + 15.5;
+            assert!(
+                (avg - expected).abs() < 6.,
+                "{:?} in band {}: average {} instead of {}",
+                kernel,
+                band,
+                avg,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn atkinson_keeps_mid_tones() {
+    // Atkinson diffuses only 6/8 of the error, highlights and shadows get lost
+    for level in [112, 128, 144] {
+        let bitmap = create_dither_image(
+            &flat(level),
+            128,
+            &DitherOptions::new(Dithering::Atkinson, false),
+        );
+        let avg = average(&bitmap);
+        assert!((avg - level as f32).abs() < 4., "{}: {}", level, avg);
+    }
+}
+
+#[test]
+fn no_dither_is_a_threshold() {
+    let bitmap = create_dither_image(
+        &flat(127),
+        128,
+        &DitherOptions::new(Dithering::NoDither, false),
+    );
+    assert_eq!(average(&bitmap), 0.);
+
+    let bitmap = create_dither_image(
+        &flat(128),
+        128,
+        &DitherOptions::new(Dithering::NoDither, false),
+    );
+    assert_eq!(average(&bitmap), 255.);
+}
+
+const ORDERED: [Dithering; 6] = [
+    Dithering::Bayer2,
+    Dithering::Bayer4,
+    Dithering::Bayer8,
+    Dithering::ClusteredDot4,
+    Dithering::ClusteredDot8,
+    Dithering::BlueNoise,
+];
+
+#[test]
+fn threshold_maps_contain_every_rank() {
+    for kernel in ORDERED {
+        let (map, size) = get_threshold_map(kernel).unwrap();
+        let mut ranks = map.to_vec();
+        ranks.sort();
+        assert_eq!(ranks, (0..size * size).map(|r| r as u8).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn ordered_dithering_preserves_flat_grey() {
+    for kernel in ORDERED {
+        let (_, size) = get_threshold_map(kernel).unwrap();
+        // one step of the map is the worst error
+        let tolerance = 255. / (size * size) as f32;
+        for level in LEVELS {
+            let bitmap = create_dither_image(&flat(level), 128, &DitherOptions::new(kernel, false));
+            let avg = average(&bitmap);
+            assert!(
+                (avg - level as f32).abs() <= tolerance,
+                "{:?} on {}: average {}",
+                kernel,
+                level,
+                avg
+            );
+        }
+    }
+}
+
+#[test]
+fn ordered_dithering_is_position_stable() {
+    // the same pixel gets the same result, whatever is around it
+    let data: Vec<u8> = (0..SIZE * SIZE).map(|n| (n * 7 % 256) as u8).collect();
+    let image = GreyBuffer::from_raw(SIZE, SIZE, data).unwrap();
+
+    for kernel in ORDERED {
+        let full = create_dither_image(&image, 128, &DitherOptions::new(kernel, false));
+
+        let mut changed = image.clone();
+        for x in 0..SIZE {
+            changed.set(x, 0, 255 - image.get(x, 0));
+        }
+        let partial = create_dither_image(&changed, 128, &DitherOptions::new(kernel, false));
+
+        for y in 1..SIZE {
+            for x in 0..SIZE {
+                assert_eq!(full.get(x, y), partial.get(x, y), "{:?}", kernel);
+            }
+        }
+    }
+}
+
+#[test]
+fn level_dithering_uses_only_the_levels_and_keeps_grey() {
+    for level_count in [5, 10, 70] {
+        let step = 255. / (level_count - 1) as f32;
+        for kernel in KERNELS.iter().chain(ORDERED.iter()) {
+            for level in LEVELS {
+                let image = create_level_dither_image(
+                    &flat(level),
+                    level_count,
+                    &DitherOptions::new(*kernel, false),
+                );
+                let values = image.as_raw();
+
+                for value in values {
+                    let k = (*value as f32 / step).round();
+                    assert_eq!(*value, (k * step).round() as u8, "{} is no level", value);
+                }
+
+                // ordered dithering can't be finer than its map
+                let tolerance = match get_threshold_map(*kernel) {
+                    Some((_, size)) => step / (size * size) as f32 + 0.5,
+                    None => 1.5,
+                };
+
+                let avg = values.iter().map(|v| *v as f32).sum::<f32>() / values.len() as f32;
+                assert!(
+                    (avg - level as f32).abs() <= tolerance,
+                    "{:?} with {} levels on {}: average {}",
+                    kernel,
+                    level_count,
+                    level,
+                    avg
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn serpentine_scan_preserves_flat_grey() {
+    for kernel in KERNELS {
+        let mut options = DitherOptions::new(kernel, false);
+        options.serpentine = true;
+        for level in LEVELS {
+            let avg = average(&create_dither_image(&flat(level), 128, &options));
+            assert!(
+                (avg - level as f32).abs() < 3.,
+                "{:?} on {}: average {}",
+                kernel,
+                level,
+                avg
+            );
+        }
+    }
+}
+
+#[test]
+fn custom_kernel_matches_built_in_kernel() {
+    let kernel = DitherKernel::parse(
+        "# Floyd-Steinberg\n\
+         divisor = 16\n\
+         1, 0, 7\n\
+         -1 1 3\n\
+         \n\
+         0 1 5   # below\n\
+         1 1 1\n",
+    )
+    .unwrap();
+    assert_eq!(kernel.divisor, 16.);
+    assert_eq!(
+        kernel.matrix,
+        vec![[1, 0, 7], [-1, 1, 3], [0, 1, 5], [1, 1, 1]]
+    );
+
+    let mut options = DitherOptions::new(Dithering::Custom, false);
+    options.custom_kernel = Some(kernel);
+    let built_in = DitherOptions::new(Dithering::FloydSteinberg, false);
+
+    for level in LEVELS {
+        assert_eq!(
+            create_dither_image(&flat(level), 128, &options),
+            create_dither_image(&flat(level), 128, &built_in)
+        );
+    }
+}
+
+#[test]
+fn custom_kernel_reaches_four_pixels() {
+    let kernel = DitherKernel::parse("4 0 1\n-4 4 1").unwrap();
+    assert_eq!(kernel.matrix, vec![[4, 0, 1], [-4, 4, 1]]);
+}
+
+#[test]
+fn custom_dithering_needs_a_kernel() {
+    let image = DynamicImage::ImageLuma8(ImageBuffer::from_pixel(8, 8, Luma([100])));
+    let mut converter = Converter::new();
+    converter.dither = Dithering::Custom;
+    assert!(matches!(
+        converter.convert(&image),
+        Err(Img2AscError::InvalidKernel { .. })
+    ));
+
+    converter.dither_kernel = Some(DitherKernel::parse("1 0 1").unwrap());
+    assert!(converter.convert(&image).is_ok());
+}
+
+#[test]
+fn custom_kernel_divisor_defaults_to_the_weight_sum() {
+    let kernel = DitherKernel::parse("1 0 2\n0 1 1\n0 3 1").unwrap();
+    assert_eq!(kernel.divisor, 4.);
+}
+
+#[test]
+fn invalid_custom_kernels_are_rejected() {
+    for text in [
+        "",
+        "1 0",
+        "1 0 x",
+        "-1 0 1",
+        "0 -1 1",
+        "divisor = 0\n1 0 1",
+        "5 0 1",
+        "-5 1 1",
+        "0 5 1",
+        "0 4000000000 1",
+    ] {
+        assert!(
+            matches!(
+                DitherKernel::parse(text),
+                Err(Img2AscError::InvalidKernel { .. })
+            ),
+            "{:?}",
+            text
+        );
+    }
+}
+
+#[test]
+fn char_levels_are_dithered_on_request() {
+    // a grey between two chars of the simple ramp
+    let image = DynamicImage::ImageLuma8(ImageBuffer::from_pixel(64, 64, Luma([100])));
+    let mut converter = Converter::new();
+    converter.ascii_width = 16;
+    converter.ascii_height = 16;
+    converter.linear = false;
+
+    let conversion = converter.convert(&image).unwrap();
+    assert!(conversion.dither.is_none());
+    let mut chars: Vec<char> = conversion.text.chars().filter(|c| *c != '\n').collect();
+    chars.dedup();
+    assert_eq!(chars.len(), 1, "{}", conversion.text);
+
+    converter.dither_levels = true;
+    let conversion = converter.convert(&image).unwrap();
+    assert!(conversion.dither.is_some());
+    let mut chars: Vec<char> = conversion.text.chars().filter(|c| *c != '\n').collect();
+    chars.sort();
+    chars.dedup();
+    assert_eq!(chars.len(), 2, "{}", conversion.text);
+}
+
+// smaller than the grey images, every pixel searches the whole palette
+fn flat_color(rgb: [f32; 3]) -> RgbaFloatBuffer {
+    let size = SIZE / 2;
+    let data = [rgb[0], rgb[1], rgb[2], 1.].repeat((size * size) as usize);
+    RgbaFloatBuffer::from_raw(size, size, data).unwrap()
+}
+
+#[test]
+fn palette_colors_stay_undithered() {
+    let palette = Palette::ansi16(&PALETTE_XTERM, ColorSpace::Oklab);
+    for kernel in KERNELS {
+        for entry in palette.entries() {
+            let image = flat_color(entry.1.map(|v| v as f32 / 255.));
+            let result =
+                create_palette_dither_image(&image, &palette, &DitherOptions::new(kernel, true));
+            assert!(result.iter().all(|pixel| pixel == entry), "{:?}", kernel);
+        }
+    }
+}
+
+#[test]
+fn palette_dithering_preserves_flat_colors() {
+    let palette = Palette::xterm256(ColorSpace::Oklab);
+    let colors = [[0.3, 0.5, 0.7], [0.9, 0.2, 0.1], [0.45, 0.45, 0.45]];
+    for kernel in KERNELS {
+        for rgb in colors {
+            let image = flat_color(rgb);
+            let result =
+                create_palette_dither_image(&image, &palette, &DitherOptions::new(kernel, false));
+            assert!(result.iter().all(|pixel| palette.entries().contains(pixel)));
+
+            for channel in 0..3 {
+                let sum: f32 = result.iter().map(|(_, rgb)| rgb[channel] as f32).sum();
+                let avg = sum / result.len() as f32;
+                assert!(
+                    (avg - rgb[channel] * 255.).abs() < 3.,
+                    "{:?} on {:?}: average {} in channel {}",
+                    kernel,
+                    rgb,
+                    avg,
+                    channel
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn palette_dithering_of_cells_keeps_the_other_layer() {
+    let palette = Palette::ansi16(&PALETTE_XTERM, ColorSpace::Oklab);
+    let colors = vec![
+        CellColor {
+            fg: AnsiColor::rgb([100, 100, 100]),
+            bg: Some(AnsiColor::rgb([60, 20, 140])),
+        };
+        64
+    ];
+    let options = DitherOptions::new(Dithering::FloydSteinberg, false);
+
+    let result = create_palette_dither_colors(&colors, 8, &palette, &options, true, false);
+    let mut fgs: Vec<u8> = result.iter().filter_map(|color| color.fg.index).collect();
+    fgs.sort();
+    fgs.dedup();
+    assert!(fgs.len() > 1, "{:?}", fgs);
+    let bg = result[0].bg;
+    assert!(result.iter().all(|color| color.bg == bg));
+    let (index, _) = palette.find_nearest([60., 20., 140.].map(|v| v / 255.));
+    assert_eq!(bg.and_then(|bg| bg.index), Some(index));
+}
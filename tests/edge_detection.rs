@@ -0,0 +1,99 @@
+use img2asc::edge_detection::{EdgeChars, EdgeDetection, EdgeOptions, create_edge_image};
+use img2asc::pixel_buffer::GreyBuffer;
+
+const SIZE: u32 = 16;
+const RAMP: [char; 3] = ['#', '+', '.'];
+
+fn step(white: impl Fn(u32, u32) -> bool) -> GreyBuffer {
+    let data = (0..SIZE * SIZE)
+        .map(|n| if white(n % SIZE, n / SIZE) { 255 } else { 0 })
+        .collect();
+    GreyBuffer::from_raw(SIZE, SIZE, data).unwrap()
+}
+
+fn options(detection: EdgeDetection) -> EdgeOptions {
+    EdgeOptions {
+        detection,
+        threshold: 64,
+        chars: EdgeChars::Ascii,
+        blend: false,
+    }
+}
+
+// the chars away from the border, where the clamped pixels bend the edges
+fn edge_chars(text: &str) -> Vec<char> {
+    let inner = 2..SIZE as usize - 2;
+    let mut chars: Vec<char> = text
+        .lines()
+        .enumerate()
+        .filter(|(y, _)| inner.contains(y))
+        .flat_map(|(_, line)| line.chars().enumerate())
+        .filter(|(x, c)| inner.contains(x) && *c != ' ')
+        .map(|(_, c)| c)
+        .collect();
+    chars.sort();
+    chars.dedup();
+    chars
+}
+
+#[test]
+fn step_edges_get_the_char_of_their_direction() {
+    let steps = [
+        (step(|x, _| x >= SIZE / 2), '|'),
+        (step(|_, y| y >= SIZE / 2), '-'),
+        (step(|x, y| x > y), '\\'),
+        (step(|x, y| x + y > SIZE), '/'),
+    ];
+    for detection in [EdgeDetection::Sobel, EdgeDetection::Canny] {
+        for (image, expected) in steps.iter() {
+            let text = create_edge_image(image, image, &RAMP, &options(detection));
+            assert_eq!(
+                edge_chars(&text),
+                vec![*expected],
+                "{:?}\n{}",
+                detection,
+                text
+            );
+        }
+    }
+}
+
+#[test]
+fn sobel_marks_both_sides_of_a_step_and_canny_one() {
+    let image = step(|x, _| x >= SIZE / 2);
+    for (detection, width) in [(EdgeDetection::Sobel, 2), (EdgeDetection::Canny, 1)] {
+        let text = create_edge_image(&image, &image, &RAMP, &options(detection));
+        for line in text.lines() {
+            let edges: Vec<usize> = line
+                .char_indices()
+                .filter(|(_, c)| *c == '|')
+                .map(|(n, _)| n)
+                .collect();
+            assert_eq!(edges.len(), width, "{:?}\n{}", detection, text);
+            assert!(edges.iter().all(|n| n.abs_diff(SIZE as usize / 2) <= 1));
+        }
+    }
+}
+
+#[test]
+fn flat_images_have_no_edges() {
+    let image = step(|_, _| true);
+    for detection in [EdgeDetection::Sobel, EdgeDetection::Canny] {
+        let text = create_edge_image(&image, &image, &RAMP, &options(detection));
+        assert!(edge_chars(&text).is_empty(), "{:?}", detection);
+    }
+}
+
+#[test]
+fn blend_fills_the_rest_with_the_ramp_and_box_draws_lines() {
+    let image = step(|x, _| x >= SIZE / 2);
+    let mut options = options(EdgeDetection::Canny);
+    options.blend = true;
+    options.chars = EdgeChars::Box;
+
+    let text = create_edge_image(&image, &image, &RAMP, &options);
+    assert_eq!(edge_chars(&text), vec!['#', '.', '│']);
+    for line in text.lines() {
+        assert!(line.starts_with('#') && line.ends_with('.'), "{}", line);
+    }
+}
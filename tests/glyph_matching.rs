@@ -0,0 +1,82 @@
+use img2asc::ascii_image::CellFit;
+use img2asc::bitmap_font::BitmapFont;
+use img2asc::glyph_matching::{GlyphMatch, create_glyph_image, get_font_chars};
+use img2asc::pixel_buffer::GreyBuffer;
+
+const LINES: [&str; 2] = ["/\\|_-#", "@ x.O="];
+
+// the glyphs of the text in black on white, like they are matched
+fn render(font: &BitmapFont, lines: &[&str]) -> GreyBuffer {
+    let cols = lines[0].chars().count() as u32;
+    let mut image = GreyBuffer::new(cols * font.width(), lines.len() as u32 * font.height());
+    for (row, line) in lines.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            for y in 0..font.height() {
+                for x in 0..font.width() {
+                    let value = if font.get(c, x, y) { 0 } else { 255 };
+                    image.set(
+                        col as u32 * font.width() + x,
+                        row as u32 * font.height() + y,
+                        value,
+                    );
+                }
+            }
+        }
+    }
+    image
+}
+
+#[test]
+fn rendered_glyphs_are_matched_exactly() {
+    let font = BitmapFont::embedded();
+    let image = render(&font, &LINES);
+    let chars = get_font_chars(&font);
+    let expected = format!("{}\n{}\n", LINES[0], LINES[1]);
+
+    for glyph_match in [GlyphMatch::Mse, GlyphMatch::Ssim] {
+        let text = create_glyph_image(&image, &font, &chars, glyph_match, CellFit::Pad);
+        assert_eq!(text, expected, "{:?}", glyph_match);
+    }
+}
+
+#[test]
+fn only_the_given_chars_are_used() {
+    let font = BitmapFont::embedded();
+    let image = render(&font, &LINES);
+    let chars = ['|', '-', ' '];
+
+    for glyph_match in [GlyphMatch::Mse, GlyphMatch::Ssim] {
+        let text = create_glyph_image(&image, &font, &chars, glyph_match, CellFit::Pad);
+        assert!(
+            text.chars().all(|c| chars.contains(&c) || c == '\n'),
+            "{:?}: {}",
+            glyph_match,
+            text
+        );
+        // the glyphs in the set still find themselves
+        let first: Vec<char> = text.lines().next().unwrap().chars().collect();
+        assert_eq!((first[2], first[4]), ('|', '-'), "{:?}", glyph_match);
+    }
+}
+
+#[test]
+fn pad_fills_partial_cells_with_white() {
+    let font = BitmapFont::embedded();
+    let mut image = render(&font, &["||"]);
+    // cut the second glyph to one column, the rest of its cell is padded
+    image = GreyBuffer::from_raw(
+        font.width() + 1,
+        font.height(),
+        (0..font.height())
+            .flat_map(|y| (0..=font.width()).map(move |x| (x, y)))
+            .map(|(x, y)| image.get(x, y))
+            .collect(),
+    )
+    .unwrap();
+    let chars = get_font_chars(&font);
+
+    let pad = create_glyph_image(&image, &font, &chars, GlyphMatch::Mse, CellFit::Pad);
+    let crop = create_glyph_image(&image, &font, &chars, GlyphMatch::Mse, CellFit::Crop);
+    assert_eq!(pad.lines().next().unwrap().chars().count(), 2);
+    assert_eq!(crop, "|\n");
+}
@@ -0,0 +1,86 @@
+use img2asc::coloring::{AnsiColor, CellColor};
+use img2asc::html_export::{HtmlOptions, TextStyle, create_html, escape_html};
+
+fn fg(rgb: [u8; 3]) -> CellColor {
+    CellColor {
+        fg: AnsiColor::rgb(rgb),
+        bg: None,
+    }
+}
+
+fn fragment() -> HtmlOptions {
+    HtmlOptions {
+        fragment: true,
+        ..HtmlOptions::default()
+    }
+}
+
+#[test]
+fn special_chars_are_escaped() {
+    assert_eq!(
+        escape_html("<a href=\"x\">&'</a>"),
+        "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+    );
+    assert_eq!(escape_html("░▒▓ plain"), "░▒▓ plain");
+}
+
+#[test]
+fn fragment_is_a_pre_with_inline_style() {
+    let html = create_html("<&>\n#  \n", None, &TextStyle::default(), &fragment());
+    assert_eq!(
+        html,
+        "<pre style=\"font-family:monospace;font-size:12px;line-height:1;color:#000000;background-color:#ffffff\">&lt;&amp;&gt;\n#  \n</pre>\n"
+    );
+}
+
+#[test]
+fn colors_are_spans_with_one_per_run() {
+    let red = fg([255, 0, 0]);
+    let blue = fg([0, 0, 255]);
+    let on_green = CellColor {
+        fg: AnsiColor::rgb([0, 0, 0]),
+        bg: Some(AnsiColor::rgb([0, 128, 0])),
+    };
+    let colors = [red, red, red, red, red, blue, on_green, blue];
+    let html = create_html(
+        " <a &\n> b\n",
+        Some(&colors),
+        &TextStyle::default(),
+        &fragment(),
+    );
+
+    // a leading space has no color, the others join the run before
+    assert!(
+        html.contains(
+            ">\u{20}<span style=\"color:#ff0000\">&lt;a &amp;</span>\n\
+             <span style=\"color:#0000ff\">&gt;</span>\
+             <span style=\"color:#000000;background-color:#008000\"> </span>\
+             <span style=\"color:#0000ff\">b</span>\n</pre>"
+        ),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn document_has_an_escaped_title_and_the_background() {
+    let style = TextStyle {
+        font_family: "'DejaVu Sans Mono', monospace".to_string(),
+        background: [0, 0, 0],
+        ..TextStyle::default()
+    };
+    let options = HtmlOptions {
+        fragment: false,
+        title: "cat & dog.png".to_string(),
+    };
+    let html = create_html("x\n", None, &style, &options);
+
+    assert!(html.starts_with("<!DOCTYPE html>\n<html>\n"));
+    assert!(html.contains("<title>cat &amp; dog.png</title>"));
+    assert!(html.contains("<body style=\"background-color:#000000\">"));
+    // white text on the dark background
+    assert!(html.contains(
+        "font-family:&#39;DejaVu Sans Mono&#39;, monospace;font-size:12px;line-height:1;color:#ffffff;background-color:#000000"
+    ));
+    assert!(html.ends_with("</pre>\n</body>\n</html>\n"));
+}
@@ -0,0 +1,98 @@
+use image::{
+    DynamicImage, ImageBuffer, Luma, LumaA, Rgb, Rgb32FImage, Rgba, Rgba32FImage, RgbaImage,
+};
+use img2asc::Converter;
+use img2asc::normalizing::create_normalized_image;
+
+const GREY: u8 = 100;
+const GREY_16: u16 = GREY as u16 * 257;
+const GREY_F: f32 = GREY as f32 / 255.;
+
+fn assert_pixel(image: &DynamicImage, expected: [f32; 4]) {
+    let normalized = create_normalized_image(image);
+    for (value, expected) in normalized.get_pixel(1, 1).iter().zip(expected) {
+        assert!(
+            (value - expected).abs() < 1e-6,
+            "{:?}: {} != {}",
+            image.color(),
+            value,
+            expected
+        );
+    }
+}
+
+fn grey_variants() -> Vec<DynamicImage> {
+    vec![
+        DynamicImage::ImageLuma8(ImageBuffer::from_pixel(4, 4, Luma([GREY]))),
+        DynamicImage::ImageLumaA8(ImageBuffer::from_pixel(4, 4, LumaA([GREY, 255]))),
+        DynamicImage::ImageRgb8(ImageBuffer::from_pixel(4, 4, Rgb([GREY; 3]))),
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([GREY, GREY, GREY, 255]))),
+        DynamicImage::ImageLuma16(ImageBuffer::from_pixel(4, 4, Luma([GREY_16]))),
+        DynamicImage::ImageLumaA16(ImageBuffer::from_pixel(4, 4, LumaA([GREY_16, u16::MAX]))),
+        DynamicImage::ImageRgb16(ImageBuffer::from_pixel(4, 4, Rgb([GREY_16; 3]))),
+        DynamicImage::ImageRgba16(ImageBuffer::from_pixel(
+            4,
+            4,
+            Rgba([GREY_16, GREY_16, GREY_16, u16::MAX]),
+        )),
+        DynamicImage::ImageRgb32F(Rgb32FImage::from_pixel(4, 4, Rgb([GREY_F; 3]))),
+        DynamicImage::ImageRgba32F(Rgba32FImage::from_pixel(
+            4,
+            4,
+            Rgba([GREY_F, GREY_F, GREY_F, 1.]),
+        )),
+    ]
+}
+
+#[test]
+fn every_layout_normalizes_to_the_same_grey() {
+    for image in grey_variants() {
+        assert_pixel(&image, [GREY_F, GREY_F, GREY_F, 1.]);
+    }
+}
+
+#[test]
+fn alpha_channels_are_kept() {
+    let la8 = DynamicImage::ImageLumaA8(ImageBuffer::from_pixel(2, 2, LumaA([255, 51])));
+    assert_pixel(&la8, [1., 1., 1., 0.2]);
+
+    let la16 = DynamicImage::ImageLumaA16(ImageBuffer::from_pixel(2, 2, LumaA([0, 13107])));
+    assert_pixel(&la16, [0., 0., 0., 0.2]);
+}
+
+#[test]
+fn color_channels_are_kept() {
+    let rgb8 = DynamicImage::ImageRgb8(ImageBuffer::from_pixel(2, 2, Rgb([255, 0, 51])));
+    assert_pixel(&rgb8, [1., 0., 0.2, 1.]);
+
+    let rgb16 = DynamicImage::ImageRgb16(ImageBuffer::from_pixel(2, 2, Rgb([0, 65535, 13107])));
+    assert_pixel(&rgb16, [0., 1., 0.2, 1.]);
+
+    let rgba32f =
+        DynamicImage::ImageRgba32F(Rgba32FImage::from_pixel(2, 2, Rgba([0.25, 0.5, 0.75, 0.5])));
+    assert_pixel(&rgba32f, [0.25, 0.5, 0.75, 0.5]);
+}
+
+#[test]
+fn sixteen_bit_keeps_precision_before_quantization() {
+    // both values round to the same 8 bit value, but not as floats
+    let low = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(2, 2, Luma([GREY_16])));
+    let high = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(2, 2, Luma([GREY_16 + 100])));
+
+    let low = create_normalized_image(&low).get_pixel(0, 0)[0];
+    let high = create_normalized_image(&high).get_pixel(0, 0)[0];
+    assert!(high > low);
+}
+
+#[test]
+fn every_layout_converts_to_the_same_ascii_image() {
+    let mut converter = Converter::new();
+    converter.ascii_width = 4;
+    converter.ascii_height = 4;
+
+    let expected = converter.convert(&grey_variants()[0]).unwrap().text;
+    for image in grey_variants() {
+        let text = converter.convert(&image).unwrap().text;
+        assert_eq!(text, expected, "{:?}", image.color());
+    }
+}
@@ -0,0 +1,114 @@
+use img2asc::Img2AscError;
+use img2asc::palette::{
+    ColorSpace, PALETTE_VGA, PALETTE_XTERM, Palette, parse_hex_color, parse_palette,
+};
+
+const SPACES: [ColorSpace; 2] = [ColorSpace::Oklab, ColorSpace::Cielab];
+
+fn srgb(rgb: [u8; 3]) -> [f32; 3] {
+    rgb.map(|v| v as f32 / 255.)
+}
+
+// colors all over the sRGB cube, between the palette colors
+fn samples() -> Vec<[f32; 3]> {
+    (0..343)
+        .map(|n| [n % 7, n / 7 % 7, n / 49].map(|v| v as f32 / 6.))
+        .collect()
+}
+
+fn distance(space: ColorSpace, a: [f32; 3], b: [f32; 3]) -> f32 {
+    let (a, b) = (space.from_srgb(a), space.from_srgb(b));
+    (0..3).map(|n| (a[n] - b[n]).powi(2)).sum()
+}
+
+#[test]
+fn xterm_palette_has_the_cube_and_the_grey_ramp() {
+    let palette = Palette::xterm256(ColorSpace::Oklab);
+    let entries = palette.entries();
+    assert_eq!(entries.len(), 240);
+    assert!(entries.iter().zip(16..=255).all(|(entry, n)| entry.0 == n));
+    assert_eq!(entries[0], (16, [0, 0, 0]));
+    assert_eq!(entries[1], (17, [0, 0, 95]));
+    assert_eq!(entries[215], (231, [255, 255, 255]));
+    assert_eq!(entries[216], (232, [8, 8, 8]));
+    assert_eq!(entries[239], (255, [238, 238, 238]));
+}
+
+#[test]
+fn palette_colors_find_themselves() {
+    for space in SPACES {
+        let palettes = [
+            Palette::xterm256(space),
+            Palette::ansi16(&PALETTE_XTERM, space),
+            Palette::ansi16(&PALETTE_VGA, space),
+        ];
+        for palette in palettes.iter() {
+            for entry in palette.entries() {
+                assert_eq!(palette.find_nearest(srgb(entry.1)), *entry, "{:?}", space);
+            }
+        }
+    }
+}
+
+#[test]
+fn nearest_color_has_the_smallest_distance_in_the_color_space() {
+    for space in SPACES {
+        let palette = Palette::ansi16(&PALETTE_XTERM, space);
+        for color in samples() {
+            let (index, rgb) = palette.find_nearest(color);
+            let found = distance(space, color, srgb(rgb));
+            for entry in palette.entries() {
+                assert!(
+                    found <= distance(space, color, srgb(entry.1)) + 1e-6,
+                    "{:?}: {:?} found {} instead of {}",
+                    space,
+                    color,
+                    index,
+                    entry.0
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn nearest_colors_keep_the_hue() {
+    // a dark blue is the darkest blue of the cube, not black
+    let palette = Palette::xterm256(ColorSpace::Oklab);
+    let (_, rgb) = palette.find_nearest(srgb([0, 0, 60]));
+    assert_eq!(rgb, [0, 0, 95]);
+
+    let palette = Palette::ansi16(&PALETTE_XTERM, ColorSpace::Oklab);
+    assert_eq!(palette.find_nearest(srgb([250, 10, 10])).0, 9);
+    assert_eq!(palette.find_nearest(srgb([128, 128, 128])).0, 8);
+}
+
+#[test]
+fn palette_files_have_16_hex_colors() {
+    assert_eq!(parse_hex_color("#cd0000"), Some([0xcd, 0, 0]));
+    assert_eq!(parse_hex_color("00CD00"), Some([0, 0xcd, 0]));
+    for value in [
+        "#cd00", "#cd00000", "#gg0000", "", "#+12345", "+12345", "+1+2+3", "##cd0000", "#-12345",
+    ] {
+        assert_eq!(parse_hex_color(value), None, "{:?}", value);
+    }
+
+    let text: Vec<String> = PALETTE_VGA
+        .iter()
+        .map(|[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b))
+        .collect();
+    assert_eq!(parse_palette(&text.join("\n")).unwrap(), PALETTE_VGA);
+    assert_eq!(parse_palette(&text.join(", ")).unwrap(), PALETTE_VGA);
+
+    for text in [
+        text[..15].join(" "),
+        text.join(" ") + " #000000",
+        "#red".to_string(),
+    ] {
+        assert!(
+            matches!(parse_palette(&text), Err(Img2AscError::InvalidPalette(_))),
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use image::error::{DecodingError, EncodingError, ImageFormatHint};
+use image::{ImageError, ImageFormat};
+use img2asc::Img2AscError;
+use img2asc::bitmap_font::BitmapFont;
+use img2asc::coloring::{AnsiColor, CellColor};
+use img2asc::pixel_buffer::PixelBuffer;
+use img2asc::png_export::{create_text_image, encode_png};
+use std::io;
+
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+#[test]
+fn image_has_a_glyph_cell_for_every_char() {
+    let font = BitmapFont::embedded();
+    let image = create_text_image("█ █\n█\n", None, &font, [255; 3]);
+    assert_eq!((image.width(), image.height()), (3 * 6, 2 * 12));
+
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            // the full blocks are black ink, the space and the end of the short line paper
+            let ink = x < 6 || (x >= 12 && y < 12);
+            let expected = if ink { BLACK } else { WHITE };
+            assert_eq!(image.get_pixel(x, y), expected, "{},{}", x, y);
+        }
+    }
+}
+
+#[test]
+fn glyphs_are_drawn_with_the_font() {
+    let font = BitmapFont::embedded();
+    let image = create_text_image("A", None, &font, [0; 3]);
+    for y in 0..12 {
+        for x in 0..6 {
+            // white on the dark background
+            let expected = if font.get('A', x, y) { WHITE } else { BLACK };
+            assert_eq!(image.get_pixel(x, y), expected, "{},{}", x, y);
+        }
+    }
+}
+
+#[test]
+fn cell_colors_are_ink_and_paper() {
+    let font = BitmapFont::embedded();
+    let colors = [
+        CellColor {
+            fg: AnsiColor::rgb([255, 0, 0]),
+            bg: None,
+        },
+        CellColor {
+            fg: AnsiColor::rgb([0, 255, 0]),
+            bg: Some(AnsiColor::rgb([0, 0, 255])),
+        },
+    ];
+    let image = create_text_image("█ \n", Some(&colors), &font, [9, 9, 9]);
+    assert_eq!(image.get_pixel(3, 5), [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(9, 5), [0, 0, 255, 255]);
+}
+
+#[test]
+fn png_decodes_to_the_same_image() {
+    let font = BitmapFont::embedded();
+    let image = create_text_image("ab\ncd\nef\n", None, &font, [255; 3]);
+    let png = encode_png(&image).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+
+    let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!(decoded.dimensions(), (2 * 6, 3 * 12));
+    assert_eq!(decoded.as_raw(), &image.to_bytes());
+}
+
+#[test]
+fn encoding_errors_are_no_decode_errors() {
+    let hint = || ImageFormatHint::Exact(ImageFormat::Png);
+    let encoding = Img2AscError::from(ImageError::Encoding(EncodingError::new(hint(), "full")));
+    assert!(matches!(encoding, Img2AscError::Encode(_)));
+    assert_eq!(encoding.exit_code(), 1);
+
+    let io = ImageError::IoError(io::Error::new(io::ErrorKind::StorageFull, "full"));
+    assert!(matches!(Img2AscError::from(io), Img2AscError::Io(_)));
+
+    let decoding = Img2AscError::from(ImageError::Decoding(DecodingError::new(hint(), "bad")));
+    assert_eq!(decoding.exit_code(), 3);
+}
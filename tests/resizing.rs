@@ -0,0 +1,80 @@
+use img2asc::pixel_buffer::{PixelBuffer, RgbaFloatBuffer};
+use img2asc::resizing::{ResizeType, create_resized_image};
+
+const FILTERS: [ResizeType; 7] = [
+    ResizeType::Area,
+    ResizeType::Bicubic,
+    ResizeType::Bilinear,
+    ResizeType::CatmullRom,
+    ResizeType::Lanczos3,
+    ResizeType::Mitchell,
+    ResizeType::NearestNeighbour,
+];
+const PIXEL: [f32; 4] = [0.2, 0.5, 0.8, 1.];
+// down, up and both
+const SIZES: [(u32, u32); 4] = [(7, 5), (40, 30), (13, 50), (1, 1)];
+
+fn flat(width: u32, height: u32) -> RgbaFloatBuffer {
+    let data = PIXEL.repeat((width * height) as usize);
+    RgbaFloatBuffer::from_raw(width, height, data).unwrap()
+}
+
+#[test]
+fn filters_preserve_a_flat_image() {
+    let image = flat(23, 17);
+    for filter in FILTERS {
+        for (width, height) in SIZES {
+            let resized = create_resized_image(&image, width, height, filter);
+            assert_eq!((resized.width(), resized.height()), (width, height));
+            for y in 0..height {
+                for x in 0..width {
+                    let pixel = resized.get_pixel(x, y);
+                    for (value, expected) in pixel.iter().zip(PIXEL) {
+                        assert!(
+                            (value - expected).abs() < 1e-4,
+                            "{:?} to {}x{} at {},{}: {:?}",
+                            filter,
+                            width,
+                            height,
+                            x,
+                            y,
+                            pixel
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn area_average_is_the_mean_of_the_covered_pixels() {
+    // a checkerboard of black and white averages to grey in every 2x2 block
+    let mut image = RgbaFloatBuffer::new(8, 6);
+    for y in 0..6 {
+        for x in 0..8 {
+            let v = ((x + y) % 2) as f32;
+            image.put_pixel(x, y, [v, v, v, 1.]);
+        }
+    }
+    let resized = create_resized_image(&image, 4, 3, ResizeType::Area);
+    for value in resized.as_raw().chunks(4) {
+        assert!((value[0] - 0.5).abs() < 1e-5, "{:?}", value);
+        assert!((value[3] - 1.).abs() < 1e-5, "{:?}", value);
+    }
+}
+
+#[test]
+fn nearest_neighbour_keeps_the_source_values() {
+    let mut image = RgbaFloatBuffer::new(5, 5);
+    for y in 0..5 {
+        for x in 0..5 {
+            image.put_pixel(x, y, [(x * 5 + y) as f32 / 24., 0., 0., 1.]);
+        }
+    }
+    let sources: Vec<f32> = image.as_raw().chunks(4).map(|p| p[0]).collect();
+    let resized = create_resized_image(&image, 12, 3, ResizeType::NearestNeighbour);
+    for pixel in resized.as_raw().chunks(4) {
+        assert!(sources.contains(&pixel[0]), "{:?}", pixel);
+    }
+}
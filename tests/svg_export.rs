@@ -0,0 +1,85 @@
+use img2asc::coloring::{AnsiColor, CellColor};
+use img2asc::html_export::TextStyle;
+use img2asc::svg_export::{SvgOptions, create_svg};
+
+fn texts(svg: &str) -> Vec<&str> {
+    svg.lines()
+        .filter(|line| line.starts_with("<text"))
+        .collect()
+}
+
+#[test]
+fn lines_are_positioned_texts_with_escaped_chars() {
+    let svg = create_svg(
+        "a<b\n   \n & \n",
+        None,
+        &TextStyle::default(),
+        &SvgOptions::default(),
+    );
+
+    // 12 pixels font size, a char is 7.2 pixels wide and the baseline 9.6 pixels low
+    assert!(svg.starts_with(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"21.6\" height=\"36\" viewBox=\"0 0 21.6 36\">\n"
+    ));
+    assert!(svg.contains("<rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n"));
+    assert!(svg.contains(
+        "<g font-family=\"monospace\" font-size=\"12\" fill=\"#000000\" xml:space=\"preserve\">\n"
+    ));
+    assert_eq!(
+        texts(&svg),
+        vec![
+            "<text x=\"0\" y=\"9.6\" textLength=\"21.6\" lengthAdjust=\"spacingAndGlyphs\">a&lt;b</text>",
+            "<text x=\"0\" y=\"33.6\" textLength=\"21.6\" lengthAdjust=\"spacingAndGlyphs\"> &amp; </text>",
+        ]
+    );
+    assert!(svg.ends_with("</g>\n</svg>\n"));
+}
+
+#[test]
+fn colors_are_runs_with_background_rects() {
+    let red = CellColor {
+        fg: AnsiColor::rgb([255, 0, 0]),
+        bg: None,
+    };
+    let on_blue = CellColor {
+        fg: AnsiColor::rgb([255, 255, 255]),
+        bg: Some(AnsiColor::rgb([0, 0, 255])),
+    };
+    let colors = [red, red, red, on_blue, on_blue];
+    let style = TextStyle {
+        font_size: 10.,
+        line_height: 1.2,
+        ..TextStyle::default()
+    };
+    let options = SvgOptions { char_width: 0.5 };
+    let svg = create_svg("x& \"\"\n", Some(&colors), &style, &options);
+
+    // the line is 12 pixels high, the text is centered in it
+    let rects: Vec<&str> = svg
+        .lines()
+        .filter(|line| line.starts_with("<rect x"))
+        .collect();
+    assert_eq!(
+        rects,
+        vec!["<rect x=\"15\" y=\"0\" width=\"10\" height=\"12\" fill=\"#0000ff\"/>"]
+    );
+    assert_eq!(
+        texts(&svg),
+        vec![
+            "<text x=\"0\" y=\"9\" textLength=\"15\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#ff0000\">x&amp; </text>",
+            "<text x=\"15\" y=\"9\" textLength=\"10\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#ffffff\">&quot;&quot;</text>",
+        ]
+    );
+}
+
+#[test]
+fn dark_backgrounds_get_white_text() {
+    let style = TextStyle {
+        background: [20, 20, 20],
+        font_family: "Fira <Mono>".to_string(),
+        ..TextStyle::default()
+    };
+    let svg = create_svg("#\n", None, &style, &SvgOptions::default());
+    assert!(svg.contains("fill=\"#141414\"/>"));
+    assert!(svg.contains("<g font-family=\"Fira &lt;Mono&gt;\" font-size=\"12\" fill=\"#ffffff\""));
+}